@@ -0,0 +1,34 @@
+use tauri::AppHandle;
+#[cfg(desktop)]
+use tauri_plugin_autostart::ManagerExt;
+
+/// Whether the launcher is registered to start in the background when the user logs in
+#[tauri::command]
+pub(crate) fn is_autostart_enabled(app: AppHandle) -> Result<bool, String> {
+    #[cfg(desktop)]
+    return app
+        .autolaunch()
+        .is_enabled()
+        .map_err(|err| format!("Failed to check autostart: {err:?}"));
+
+    #[cfg(mobile)]
+    Ok(false)
+}
+
+/// Registers or unregisters the launcher from the OS login items
+/// (the XDG autostart entry on Linux, a launch agent on macOS and the registry on Windows)
+#[tauri::command]
+pub(crate) fn set_autostart(app: AppHandle, enabled: bool) -> Result<(), String> {
+    #[cfg(desktop)]
+    {
+        let autolaunch = app.autolaunch();
+        let result = match enabled {
+            true => autolaunch.enable(),
+            false => autolaunch.disable(),
+        };
+        return result.map_err(|err| format!("Failed to set autostart: {err:?}"));
+    }
+
+    #[cfg(mobile)]
+    Err(String::from("Autostart is not supported on mobile"))
+}
@@ -1,22 +1,43 @@
 use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 
-use holochain_client::AppInfo;
+use holochain_client::{AppInfo, InstalledAppId};
 use holochain_types::prelude::{
     AppBundle, ExternIO, SerializedBytes, Signal, UnsafeBytes, ZomeName,
 };
 use holochain_types::web_app::WebAppBundle;
+#[cfg(desktop)]
+use serde_json::Value;
 use tauri::{AppHandle, Manager, Runtime, Window, WindowBuilder, WindowUrl};
 #[cfg(desktop)]
+use tauri_plugin_autostart::MacosLauncher;
+#[cfg(desktop)]
 use tauri_plugin_cli::CliExt;
-use tauri_plugin_holochain::{setup_holochain, HolochainExt};
+#[cfg(target_os = "android")]
+use tauri_plugin_deep_link::DeepLinkExt;
+#[cfg(desktop)]
+use tauri_plugin_holochain::{parse_dev_bundle_arg, parse_dev_server_arg, HRL_SCHEME};
+use tauri_plugin_holochain::{
+    enable_dev_mode, handle_deep_link, setup_holochain, watch_app_bundle, HolochainExt,
+    UpdateAppError,
+};
 use tauri_plugin_holochain_notification::{
     provider_fcm_app_bundle, provider_fcm_recipient_app_bundle, setup_notifications,
 };
 
+mod autostart;
+#[cfg(desktop)]
+mod settings;
+#[cfg(desktop)]
+mod tray;
+#[cfg(desktop)]
+mod url_scheme;
+
 const NOTIFICATIONS_RECIPIENT_APP_ID: &'static str = "notifications_fcm_recipient";
 const NOTIFICATIONS_PROVIDER_APP_ID: &'static str = "notifications_provider_fcm";
 const FCM_PROJECT_ID: &'static str = "rostanga-ce319";
+/// The launcher's own window, the only one authorized to act on all the apps
+const WELCOME_WINDOW_LABEL: &'static str = "Welcome";
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -39,13 +60,42 @@ pub fn run() {
 
     #[cfg(desktop)]
     {
-        builder = builder.plugin(tauri_plugin_cli::init());
+        builder = builder
+            .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+                // Forward the deep links that another instance was launched with
+                for arg in argv.into_iter().skip(1) {
+                    if arg.starts_with(&format!("{HRL_SCHEME}://")) {
+                        if let Err(err) = handle_deep_link(app, arg) {
+                            log::error!("Failed to handle deep link: {err:?}");
+                        }
+                    }
+                }
+            }))
+            .plugin(tauri_plugin_cli::init())
+            .plugin(tauri_plugin_autostart::init(
+                MacosLauncher::LaunchAgent,
+                Some(vec!["--background"]),
+            ));
+    }
+    // Android hands the links that the app is opened with in an intent, which only this plugin receives
+    #[cfg(target_os = "android")]
+    {
+        builder = builder.plugin(tauri_plugin_deep_link::init());
     }
 
     builder
-        .invoke_handler(tauri::generate_handler![launch_gather, is_android])
-        .plugin(tauri_plugin_holochain::init(PathBuf::from("holochain")))
+        .invoke_handler(tauri::generate_handler![
+            launch_gather,
+            is_android,
+            autostart::is_autostart_enabled,
+            autostart::set_autostart
+        ])
+        .plugin(tauri_plugin_holochain::init(
+            PathBuf::from("holochain"),
+            vec![String::from(WELCOME_WINDOW_LABEL)],
+        ))
         .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_dialog::init())
         // .plugin(tauri_plugin_holochain_notification::init())
         .setup(|app| {
             log::info!("Start tauri setup");
@@ -64,11 +114,66 @@ pub fn run() {
             //            #[cfg(mobile)]
             //            setup_notifications(app.handle())?;
 
+            // Queued until holochain is ready, like the ones passed as arguments on desktop
+            #[cfg(target_os = "android")]
+            {
+                for url in app.deep_link().get_current()?.unwrap_or_default() {
+                    if let Err(err) = handle_deep_link(app.handle(), url.to_string()) {
+                        log::error!("Failed to handle deep link: {err:?}");
+                    }
+                }
+                let h = app.handle().clone();
+                app.listen_global("deep-link://new-url", move |event| {
+                    let Ok(urls) = serde_json::from_str::<Vec<String>>(event.payload()) else {
+                        return;
+                    };
+                    for url in urls {
+                        if let Err(err) = handle_deep_link(&h, url) {
+                            log::error!("Failed to handle deep link: {err:?}");
+                        }
+                    }
+                });
+            }
+
+            let mut background = false;
+            let mut dev_mode = DevMode::default();
+            #[cfg(desktop)]
+            {
+                if let Err(err) = url_scheme::register_hrl_scheme(app.handle()) {
+                    log::error!("Failed to register the {HRL_SCHEME} scheme: {err:?}");
+                }
+                let args = app.cli().matches()?.args;
+                if let Some(m) = args.get("url") {
+                    if let Value::String(url) = m.value.clone() {
+                        // Queued until holochain is ready
+                        if let Err(err) = handle_deep_link(app.handle(), url) {
+                            log::error!("Failed to handle deep link: {err:?}");
+                        }
+                    }
+                }
+                // Set when launched on login by the autostart
+                if let Some(m) = args.get("background") {
+                    background = m.value.eq(&Value::Bool(true));
+                }
+                if background {
+                    tray::set_launched_in_background();
+                }
+                for arg in cli_values(&args, "dev-ui") {
+                    dev_mode.dev_uis.push(parse_dev_server_arg(arg.as_str())?);
+                }
+                for arg in cli_values(&args, "dev-happ") {
+                    dev_mode.dev_bundles.push(parse_dev_bundle_arg(arg.as_str())?);
+                }
+            }
+
+            #[cfg(desktop)]
+            tray::create_tray(app.handle())?;
+
             let h = app.handle().clone();
             let h2 = app.handle().clone();
 
             tauri::async_runtime::spawn(async move {
-                match setup(h).await {
+                match setup(h, background, dev_mode).await {
                     Ok(_) => {}
                     Err(err) => {
                         if let Err(err) =
@@ -80,10 +185,10 @@ pub fn run() {
                 }
             });
 
-            if is_first_run()? {
+            if is_first_run()? && !background {
                 let mut window_builder = WindowBuilder::new(
                     app.handle(),
-                    "Welcome",
+                    WELCOME_WINDOW_LABEL,
                     WindowUrl::App("index.html".into()),
                 );
 
@@ -98,14 +203,99 @@ pub fn run() {
             log::info!("Finishing setup");
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app, event| {
+            #[cfg(desktop)]
+            if let tauri::RunEvent::ExitRequested { api, .. } = &event {
+                // Keep the conductor running headless when all the windows are closed
+                if tray::should_keep_running() {
+                    api.prevent_exit();
+                }
+            }
+            // macOS and iOS hand the links to the running app instead of passing them as arguments
+            #[cfg(any(target_os = "macos", target_os = "ios"))]
+            if let tauri::RunEvent::Opened { urls } = &event {
+                for url in urls {
+                    if let Err(err) = handle_deep_link(_app, url.to_string()) {
+                        log::error!("Failed to handle deep link: {err:?}");
+                    }
+                }
+            }
+        });
 }
 
-async fn setup<R: Runtime>(app: AppHandle<R>) -> anyhow::Result<()> {
+/// The apps being developed, as given in the command line
+#[derive(Default)]
+struct DevMode {
+    /// The apps whose UI is served from a dev server
+    dev_uis: Vec<(InstalledAppId, url::Url)>,
+    /// The apps that get updated whenever their bundle changes
+    dev_bundles: Vec<(InstalledAppId, PathBuf)>,
+}
+
+/// The values of a command line argument that can be given multiple times
+#[cfg(desktop)]
+fn cli_values(args: &HashMap<String, tauri_plugin_cli::ArgData>, name: &str) -> Vec<String> {
+    let Some(m) = args.get(name) else {
+        return vec![];
+    };
+    let values = match m.value.clone() {
+        Value::Array(values) => values,
+        value => vec![value],
+    };
+    values
+        .into_iter()
+        .filter_map(|value| match value {
+            Value::String(value) => Some(value),
+            _ => None,
+        })
+        .collect()
+}
+
+/// In background mode holochain is set up without opening any window
+async fn setup<R: Runtime>(
+    app: AppHandle<R>,
+    background: bool,
+    dev_mode: DevMode,
+) -> anyhow::Result<()> {
+    for (app_id, dev_server_url) in dev_mode.dev_uis {
+        enable_dev_mode(app_id, dev_server_url).await;
+    }
+
     setup_holochain(app.clone()).await?;
     log::info!("Successfully set up holochain");
 
+    for (app_id, bundle_path) in dev_mode.dev_bundles {
+        watch_app_bundle(app.clone(), app_id, bundle_path);
+    }
+
+    app.listen_global("app-uninstalled", move |event| {
+        if let Ok(app_id) = serde_json::from_str::<String>(event.payload()) {
+            if let Err(err) = remove_installed_app(&app_id) {
+                log::error!("Failed to remove uninstalled app {app_id} from the setup: {err:?}");
+            }
+        }
+    });
+
+    #[cfg(desktop)]
+    {
+        if let Err(err) = tray::refresh_tray_menu(&app).await {
+            log::error!("Failed to refresh the tray menu: {err:?}");
+        }
+        for event in ["app-installed", "app-uninstalled"] {
+            let h = app.clone();
+            app.listen_global(event, move |_| {
+                let h = h.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(err) = tray::refresh_tray_menu(&h).await {
+                        log::error!("Failed to refresh the tray menu: {err:?}");
+                    }
+                });
+            });
+        }
+    }
+
     let mut initial_apps = initial_apps();
 
     let mut apps_hashes: BTreeMap<String, String> = BTreeMap::new();
@@ -114,6 +304,10 @@ async fn setup<R: Runtime>(app: AppHandle<R>) -> anyhow::Result<()> {
         apps_hashes.insert(app_id.clone(), hash.clone());
     }
     if !is_first_run()? {
+        // Installations with deferred membrane proofs that were never completed start over
+        let installed_apps = install_initial_apps_if_necessary(&app, &mut initial_apps).await?;
+        log::info!("Installed apps: {installed_apps:?}");
+
         let mut mock_initial: BTreeMap<String, String> = BTreeMap::new();
         mock_initial.insert(
             "gather".into(),
@@ -138,7 +332,14 @@ async fn setup<R: Runtime>(app: AppHandle<R>) -> anyhow::Result<()> {
                     // Update
                     match initial_app {
                         InitialApp::WebApp(web_app) => {
-                            app.holochain()?.update_web_app(app_id, web_app).await?;
+                            match app.holochain()?.update_web_app(app_id.clone(), web_app).await {
+                                Ok(()) => {}
+                                // The bundled apps keep their app id, so they can't be migrated side by side
+                                Err(UpdateAppError::IntegrityZomesChanged(_, changes)) => {
+                                    log::warn!("Can't update app {app_id}, its integrity zomes changed: {changes:?}");
+                                }
+                                Err(err) => return Err(err.into()),
+                            }
                         }
                         _ => {}
                     }
@@ -146,9 +347,12 @@ async fn setup<R: Runtime>(app: AppHandle<R>) -> anyhow::Result<()> {
             }
         }
 
-        app.holochain()?.open_app(String::from("gather")).await?;
+        if !background {
+            app.holochain()?.reopen_apps().await?;
+            app.holochain()?.open_app(String::from("gather")).await?;
+        }
     } else {
-        let installed_apps = install_initial_apps_if_necessary(&app, initial_apps).await?;
+        let installed_apps = install_initial_apps_if_necessary(&app, &mut initial_apps).await?;
         log::info!("Installed apps: {installed_apps:?}");
     }
     save_installed_apps(apps_hashes)?;
@@ -261,9 +465,10 @@ fn initial_apps() -> BTreeMap<String, (String, InitialApp)> {
     apps
 }
 
+/// Installs the initial apps that are not installed in the conductor, removing them from the given ones
 pub async fn install_initial_apps_if_necessary<R: Runtime>(
     app_handle: &AppHandle<R>,
-    apps: BTreeMap<String, (String, InitialApp)>,
+    apps: &mut BTreeMap<String, (String, InitialApp)>,
 ) -> anyhow::Result<Vec<AppInfo>> {
     let mut admin_ws = app_handle.holochain()?.admin_websocket().await?;
 
@@ -274,12 +479,19 @@ pub async fn install_initial_apps_if_necessary<R: Runtime>(
 
     let mut new_apps: Vec<AppInfo> = Vec::new();
 
-    for (app_id, initial_app) in apps {
-        if installed_apps
-            .iter()
-            .find(|app| app.installed_app_id.eq(&app_id))
-            .is_none()
-        {
+    let missing_app_ids: Vec<String> = apps
+        .keys()
+        .filter(|app_id| {
+            installed_apps
+                .iter()
+                .find(|app| app.installed_app_id.eq(*app_id))
+                .is_none()
+        })
+        .cloned()
+        .collect();
+
+    for app_id in missing_app_ids {
+        if let Some(initial_app) = apps.remove(&app_id) {
             let app_info = match initial_app {
                 (_, InitialApp::App(bundle)) => {
                     app_handle
@@ -288,6 +500,20 @@ pub async fn install_initial_apps_if_necessary<R: Runtime>(
                         .await
                 }
                 (_, InitialApp::WebApp(bundle)) => {
+                    let ui_metadata = app_handle
+                        .holochain()?
+                        .web_app_ui_metadata(&bundle)
+                        .await?;
+
+                    if ui_metadata.deferred_membrane_proofs {
+                        // The app's UI provides the membrane proofs, which completes the installation
+                        app_handle
+                            .holochain()?
+                            .install_web_app_with_deferred_membrane_proofs(app_id, bundle, None)
+                            .await?;
+                        continue;
+                    }
+
                     app_handle
                         .holochain()?
                         .install_web_app(app_id, bundle, HashMap::new(), None)
@@ -382,7 +608,7 @@ pub(crate) async fn launch_gather(
 fn is_first_run() -> anyhow::Result<bool> {
     Ok(!setup_file_path()?.exists())
 }
-fn setup_file_path() -> anyhow::Result<PathBuf> {
+fn app_root_dir() -> anyhow::Result<PathBuf> {
     let root = app_dirs2::app_root(
         app_dirs2::AppDataType::UserData,
         &app_dirs2::AppInfo {
@@ -391,7 +617,10 @@ fn setup_file_path() -> anyhow::Result<PathBuf> {
         },
     )?;
 
-    Ok(root.join("setup"))
+    Ok(root)
+}
+fn setup_file_path() -> anyhow::Result<PathBuf> {
+    Ok(app_root_dir()?.join("setup"))
 
     //    app.path()
     //        .app_data_dir()
@@ -415,3 +644,10 @@ fn get_installed_apps() -> anyhow::Result<BTreeMap<String, String>> {
 
     Ok(apps)
 }
+fn remove_installed_app(app_id: &String) -> anyhow::Result<()> {
+    let mut apps = get_installed_apps()?;
+
+    apps.remove(app_id);
+
+    save_installed_apps(apps)
+}
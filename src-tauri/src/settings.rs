@@ -0,0 +1,38 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app_root_dir;
+
+/// Launcher settings that the user can change from the tray menu
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct LauncherSettings {
+    /// Keep holochain running headless when all the windows are closed.
+    /// Off until the user opts in, so that closing the last window quits the launcher as usual
+    pub run_in_background: bool,
+}
+
+fn settings_file_path() -> anyhow::Result<PathBuf> {
+    Ok(app_root_dir()?.join("settings.json"))
+}
+
+pub fn get_settings() -> anyhow::Result<LauncherSettings> {
+    let path = settings_file_path()?;
+
+    if !path.exists() {
+        return Ok(LauncherSettings::default());
+    }
+
+    let s = std::fs::read_to_string(path)?;
+    let settings: LauncherSettings = serde_json::from_str(s.as_str())?;
+
+    Ok(settings)
+}
+
+pub fn save_settings(settings: &LauncherSettings) -> anyhow::Result<()> {
+    let data = serde_json::to_string(settings)?;
+    std::fs::write(settings_file_path()?, data.as_bytes())?;
+
+    Ok(())
+}
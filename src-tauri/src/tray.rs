@@ -0,0 +1,168 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use holochain_client::InstalledAppId;
+use tauri::{
+    menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem},
+    tray::TrayIconBuilder,
+    AppHandle, Manager, Runtime,
+};
+use tauri_plugin_holochain::HolochainExt;
+
+use crate::settings::{get_settings, save_settings};
+
+const TRAY_ID: &'static str = "main";
+const QUIT_ID: &'static str = "quit";
+const RUN_IN_BACKGROUND_ID: &'static str = "run-in-background";
+const OPEN_APP_ID_PREFIX: &'static str = "open-app:";
+
+/// Set when the user quits from the tray, so that the exit is not prevented by the background mode
+static QUITTING: AtomicBool = AtomicBool::new(false);
+
+/// Set when launched on login by the autostart, to keep the peer running whatever the background setting
+static LAUNCHED_IN_BACKGROUND: AtomicBool = AtomicBool::new(false);
+
+/// Keeps the process running after its windows are closed, for the launches on login by the autostart
+pub fn set_launched_in_background() {
+    LAUNCHED_IN_BACKGROUND.store(true, Ordering::Relaxed);
+}
+
+/// Creates the tray icon, whose menu lists the installed apps once holochain is ready
+pub fn create_tray<R: Runtime>(app: &AppHandle<R>) -> anyhow::Result<()> {
+    let menu = tray_menu(app, vec![])?;
+
+    let mut tray_builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("röstånga")
+        .menu(&menu)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id.as_ref()));
+
+    if let Some(icon) = app.default_window_icon() {
+        tray_builder = tray_builder.icon(icon.clone());
+    }
+    tray_builder.build(app)?;
+
+    Ok(())
+}
+
+/// Rebuilds the tray menu with the apps that have a UI, to be called whenever apps are installed or uninstalled
+pub async fn refresh_tray_menu<R: Runtime>(app: &AppHandle<R>) -> anyhow::Result<()> {
+    let holochain = app.holochain()?;
+    let mut admin_ws = holochain.admin_websocket().await?;
+
+    let apps = admin_ws
+        .list_apps(None)
+        .await
+        .map_err(|err| tauri_plugin_holochain::Error::ConductorApiError(err))?;
+
+    let app_ids: Vec<InstalledAppId> = apps
+        .into_iter()
+        .map(|app_info| app_info.installed_app_id)
+        .filter(|app_id| holochain.filesystem.ui_store().has_ui(app_id))
+        .collect();
+
+    let menu = tray_menu(app, app_ids)?;
+
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        tray.set_menu(Some(menu))?;
+    }
+
+    Ok(())
+}
+
+/// Whether the exit that was requested should be prevented to keep holochain running in the background
+pub fn should_keep_running() -> bool {
+    if QUITTING.load(Ordering::Relaxed) {
+        return false;
+    }
+    if LAUNCHED_IN_BACKGROUND.load(Ordering::Relaxed) {
+        return true;
+    }
+
+    match get_settings() {
+        Ok(settings) => settings.run_in_background,
+        Err(err) => {
+            log::error!("Failed to read the settings: {err:?}");
+            false
+        }
+    }
+}
+
+fn tray_menu<R: Runtime>(
+    app: &AppHandle<R>,
+    app_ids: Vec<InstalledAppId>,
+) -> anyhow::Result<Menu<R>> {
+    let menu = Menu::new(app);
+
+    if app_ids.is_empty() {
+        menu.append(&MenuItem::new(app, "No apps available", false, None))?;
+    }
+    for app_id in app_ids {
+        menu.append(&MenuItem::with_id(
+            app,
+            format!("{OPEN_APP_ID_PREFIX}{app_id}"),
+            app_id.as_str(),
+            true,
+            None,
+        ))?;
+    }
+
+    let run_in_background = get_settings()?.run_in_background;
+
+    menu.append(&PredefinedMenuItem::separator(app))?;
+    menu.append(&CheckMenuItem::with_id(
+        app,
+        RUN_IN_BACKGROUND_ID,
+        "Keep running in the background",
+        true,
+        run_in_background,
+        None,
+    ))?;
+    menu.append(&MenuItem::with_id(app, QUIT_ID, "Quit", true, None))?;
+
+    Ok(menu)
+}
+
+fn handle_menu_event<R: Runtime>(app: &AppHandle<R>, id: &str) {
+    match id {
+        QUIT_ID => quit(app),
+        RUN_IN_BACKGROUND_ID => {
+            if let Err(err) = toggle_run_in_background() {
+                log::error!("Failed to toggle the background mode: {err:?}");
+            }
+        }
+        _ => {
+            if let Some(app_id) = id.strip_prefix(OPEN_APP_ID_PREFIX) {
+                let app = app.clone();
+                let app_id = app_id.to_string();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(err) = open_app(&app, app_id.clone()).await {
+                        log::error!("Failed to open app {app_id} from the tray: {err:?}");
+                    }
+                });
+            }
+        }
+    }
+}
+
+async fn open_app<R: Runtime>(app: &AppHandle<R>, app_id: InstalledAppId) -> anyhow::Result<()> {
+    app.holochain()?.open_app(app_id).await?;
+    Ok(())
+}
+
+fn toggle_run_in_background() -> anyhow::Result<()> {
+    let mut settings = get_settings()?;
+    settings.run_in_background = !settings.run_in_background;
+    save_settings(&settings)
+}
+
+/// Quits the launcher, stopping holochain, saving the state of the open windows so that they get reopened
+fn quit<R: Runtime>(app: &AppHandle<R>) {
+    QUITTING.store(true, Ordering::Relaxed);
+
+    if let Ok(holochain) = app.holochain() {
+        if let Err(err) = holochain.save_windows_state() {
+            log::error!("Failed to save the state of the windows: {err:?}");
+        }
+    }
+
+    app.exit(0);
+}
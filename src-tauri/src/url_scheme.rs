@@ -0,0 +1,73 @@
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_holochain::HRL_SCHEME;
+
+/// Registers the launcher as the handler of the `hrl://` links with the OS,
+/// so that clicking one launches it with the link as its argument.
+/// The desktop file is rewritten on every start, so that the registration follows the executable when it moves,
+/// but the launcher is only made the default handler when there is none yet.
+/// On macOS the scheme is declared in the Info.plist of the bundle instead, and links arrive as `RunEvent::Opened`
+#[cfg(target_os = "linux")]
+pub fn register_hrl_scheme<R: Runtime>(app: &AppHandle<R>) -> anyhow::Result<()> {
+    use tauri::Manager;
+
+    // AppImages are mounted in a different path on every launch
+    let exec = match std::env::var("APPIMAGE") {
+        Ok(appimage) => std::path::PathBuf::from(appimage),
+        Err(_) => std::env::current_exe()?,
+    };
+
+    let desktop_file_name = format!("{}-handler.desktop", app.config().tauri.bundle.identifier);
+    let applications_dir = app.path().data_dir()?.join("applications");
+    std::fs::create_dir_all(&applications_dir)?;
+    std::fs::write(
+        applications_dir.join(&desktop_file_name),
+        format!(
+            "[Desktop Entry]\nType=Application\nName={}\nExec=\"{}\" %u\nTerminal=false\nNoDisplay=true\nMimeType=x-scheme-handler/{HRL_SCHEME};\n",
+            app.package_info().name,
+            exec.display()
+        ),
+    )?;
+
+    // Only claim the scheme if no handler is set, so that the one the user chose is respected
+    let mime_type = format!("x-scheme-handler/{HRL_SCHEME}");
+    let current_handler = std::process::Command::new("xdg-mime")
+        .args(["query", "default", mime_type.as_str()])
+        .output()?;
+    if !String::from_utf8_lossy(&current_handler.stdout)
+        .trim()
+        .is_empty()
+    {
+        return Ok(());
+    }
+
+    let status = std::process::Command::new("xdg-mime")
+        .args(["default", desktop_file_name.as_str(), mime_type.as_str()])
+        .status()?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("xdg-mime exited with {status}"));
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+pub fn register_hrl_scheme<R: Runtime>(_app: &AppHandle<R>) -> anyhow::Result<()> {
+    use winreg::{enums::HKEY_CURRENT_USER, RegKey};
+
+    let exec = std::env::current_exe()?;
+
+    let (scheme_key, _) = RegKey::predef(HKEY_CURRENT_USER)
+        .create_subkey(format!("Software\\Classes\\{HRL_SCHEME}"))?;
+    scheme_key.set_value("", &format!("URL:{HRL_SCHEME} protocol"))?;
+    scheme_key.set_value("URL Protocol", &"")?;
+
+    let (command_key, _) = scheme_key.create_subkey("shell\\open\\command")?;
+    command_key.set_value("", &format!("\"{}\" \"%1\"", exec.display()))?;
+
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+pub fn register_hrl_scheme<R: Runtime>(_app: &AppHandle<R>) -> anyhow::Result<()> {
+    Ok(())
+}
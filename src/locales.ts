@@ -1,5 +1,6 @@
 import { configureLocalization } from "@lit/localize";
 import { invoke } from "@tauri-apps/api/core";
+import { event } from "@tauri-apps/api";
 
 export async function setLocale() {
   let locales: string[] = await invoke("plugin:holochain|get_locales", {});
@@ -35,11 +36,18 @@ export async function setLocale() {
     },
   });
 
-  for (const locale of locales) {
-    if (allLocales.includes(locale)) {
-      localization.setLocale(locale);
+  const applyLocales = (locales: string[]) => {
+    for (const locale of locales) {
+      if (allLocales.includes(locale)) {
+        localization.setLocale(locale);
 
-      return;
+        return;
+      }
     }
-  }
+  };
+  applyLocales(locales);
+
+  event.listen("locale-changed", (e) => {
+    applyLocales(e.payload as string[]);
+  });
 }
@@ -1,20 +1,9 @@
-use std::collections::HashMap;
-
 use hc_zome_trait_pending_notifications::{GetNotificationInput, Notification};
-use holochain_client::{sign_zome_call_with_client, AdminWebsocket, AppWebsocket};
-use holochain_conductor_api::CellInfo;
-use holochain_nonce::fresh_nonce;
-use holochain_types::{
-    prelude::{
-        AnyDhtHash, AnyDhtHashB64, AppBundle, CellId, DnaHash, DnaHashB64, ExternIO, FunctionName,
-        Timestamp, ZomeCallUnsigned, ZomeName,
-    },
-    web_app::WebAppBundle,
-};
+use holochain_types::prelude::{AnyDhtHash, FunctionName};
 use hrl::Hrl;
 
 use serde::{Deserialize, Serialize};
-use tauri_plugin_holochain::{launch, RunningHolochainInfo};
+use tauri_plugin_holochain::{call_hrl_resolver, launch, preferred_locale, RunningHolochainInfo};
 use tauri_plugin_notification::*;
 
 use jni::objects::JClass;
@@ -57,56 +46,21 @@ async fn modify(notification: NotificationData) -> crate::Result<NotificationDat
         crate::Error::ModifyNotificationError(String::from("Failed to run holochain"))
     })?;
 
-    let mut admin_ws = AdminWebsocket::connect(format!("ws://localhost:{}", info.admin_port))
-        .await
-        .map_err(|err| {
-            crate::Error::ModifyNotificationError(String::from(
-                "Could not connect to admin interface",
-            ))
-        })?;
-    let mut app_ws = AppWebsocket::connect(format!("ws://localhost:{}", info.app_port))
-        .await
-        .map_err(|err| {
-            crate::Error::ModifyNotificationError("Could not connect to app interface".into())
-        })?;
-
-    let apps = admin_ws
-        .list_apps(None)
-        .await
-        .map_err(|err| crate::Error::ModifyNotificationError("Failed to list apps".into()))?;
-
-    let dna_hash = DnaHash::from(hrl_body.dna_hash);
-
-    let cell_id = apps
-        .into_iter()
-        .find_map(|app_info| {
-            app_info.cell_info.values().find_map(|cells| {
-                cells.iter().find_map(|cell_info| match cell_info {
-                    CellInfo::Provisioned(cell) => match cell.cell_id.dna_hash().eq(&dna_hash) {
-                        true => Some(cell.cell_id.clone()),
-                        false => None,
-                    },
-                    CellInfo::Cloned(cell) => match cell.cell_id.dna_hash().eq(&dna_hash) {
-                        true => Some(cell.cell_id.clone()),
-                        false => None,
-                    },
-                    _ => None,
-                })
-            })
-        })
-        .ok_or(crate::Error::ModifyNotificationError(
-            "No app with this dna hash".into(),
-        ))?;
+    let notification_hash = AnyDhtHash::from(hrl_body.resource_hash.clone());
 
-    let notification_hash = AnyDhtHash::from(hrl_body.resource_hash);
+    let locale = preferred_locale(&info.filesystem).map_err(|err| {
+        crate::Error::ModifyNotificationError(format!(
+            "Failed to get the preferred locale: {err:?}"
+        ))
+    })?;
 
     let input = GetNotificationInput {
         notification_hash: notification_hash.clone(),
-        locale: String::from("sv"),
+        locale,
     };
 
     let mut maybe_pending_notification =
-        get_pending_notification(&info, &mut app_ws, cell_id.clone(), input.clone()).await;
+        get_pending_notification(&info, &hrl_body, input.clone()).await;
 
     match maybe_pending_notification {
         Ok(Some(_)) => {}
@@ -114,7 +68,7 @@ async fn modify(notification: NotificationData) -> crate::Result<NotificationDat
             std::thread::sleep(std::time::Duration::from_secs(1));
 
             maybe_pending_notification =
-                get_pending_notification(&info, &mut app_ws, cell_id.clone(), input.clone()).await;
+                get_pending_notification(&info, &hrl_body, input.clone()).await;
         }
     }
     match maybe_pending_notification {
@@ -123,7 +77,7 @@ async fn modify(notification: NotificationData) -> crate::Result<NotificationDat
             std::thread::sleep(std::time::Duration::from_secs(1));
 
             maybe_pending_notification =
-                get_pending_notification(&info, &mut app_ws, cell_id.clone(), input.clone()).await;
+                get_pending_notification(&info, &hrl_body, input.clone()).await;
         }
     }
     match maybe_pending_notification {
@@ -132,7 +86,7 @@ async fn modify(notification: NotificationData) -> crate::Result<NotificationDat
             std::thread::sleep(std::time::Duration::from_secs(1));
 
             maybe_pending_notification =
-                get_pending_notification(&info, &mut app_ws, cell_id.clone(), input.clone()).await;
+                get_pending_notification(&info, &hrl_body, input.clone()).await;
         }
     }
     match maybe_pending_notification {
@@ -141,7 +95,7 @@ async fn modify(notification: NotificationData) -> crate::Result<NotificationDat
             std::thread::sleep(std::time::Duration::from_secs(1));
 
             maybe_pending_notification =
-                get_pending_notification(&info, &mut app_ws, cell_id.clone(), input.clone()).await;
+                get_pending_notification(&info, &hrl_body, input.clone()).await;
         }
     }
     match maybe_pending_notification {
@@ -150,7 +104,7 @@ async fn modify(notification: NotificationData) -> crate::Result<NotificationDat
             std::thread::sleep(std::time::Duration::from_secs(1));
 
             maybe_pending_notification =
-                get_pending_notification(&info, &mut app_ws, cell_id.clone(), input.clone()).await;
+                get_pending_notification(&info, &hrl_body, input.clone()).await;
         }
     }
 
@@ -213,34 +167,10 @@ async fn modify(notification: NotificationData) -> crate::Result<NotificationDat
 
 async fn get_pending_notification(
     info: &RunningHolochainInfo,
-    app_ws: &mut AppWebsocket,
-    cell_id: CellId,
+    hrl: &Hrl,
     input: GetNotificationInput,
 ) -> crate::Result<Option<Notification>> {
-    let (nonce, expires_at) = fresh_nonce(Timestamp::now())
-        .map_err(|err| crate::Error::NonceError(format!("{err:?}")))?;
-
-    let zome_call_unsigned = ZomeCallUnsigned {
-        provenance: cell_id.agent_pubkey().clone(),
-        cell_id,
-        zome_name: ZomeName::from("gather"), // TODO: remove hardcoded zome name
-        fn_name: FunctionName::from("get_notification"),
-        cap_secret: None,
-        payload: ExternIO::encode(input)?,
-        nonce,
-        expires_at,
-    };
-
-    let zome_call = sign_zome_call_with_client(zome_call_unsigned, &info.lair_client.clone())
-        .await
-        .map_err(|err| crate::Error::SignZomeCallError(err))?;
-
-    // Hardcoded zome name
-    // TODO: add HRL resolver to get which zome to call fetch_notification on?
-    let response = app_ws
-        .call_zome(zome_call)
-        .await
-        .map_err(|err| crate::Error::ConductorApiError(err))?;
-    let maybe_pending_notification: Option<Notification> = response.decode()?;
+    let maybe_pending_notification: Option<Notification> =
+        call_hrl_resolver(info, hrl, FunctionName::from("get_notification"), input).await?;
     Ok(maybe_pending_notification)
 }
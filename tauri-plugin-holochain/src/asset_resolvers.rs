@@ -0,0 +1,291 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Read,
+    sync::Arc,
+};
+
+use futures::future::BoxFuture;
+use holochain_client::InstalledAppId;
+use holochain_types::web_app::WebAppBundle;
+use hyper::{client::HttpConnector, header::CONTENT_TYPE, Client, StatusCode};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use tokio::sync::RwLock;
+
+use crate::{
+    assets::{Asset, ContentEncoding},
+    filesystem::{FileSystem, FileSystemError, UiMetadata, UI_METADATA_FILE},
+};
+
+/// Source of the assets of an app's UI, used both by the http server and the `happ` protocol
+pub trait AssetResolver: Send + Sync {
+    /// Resolves the asset at the given path of the app's UI, which is empty for its root,
+    /// and with the query of the request, for the resolvers that forward it.
+    /// The asset can be returned in any of the accepted encodings
+    fn resolve_asset<'a>(
+        &'a self,
+        app_id: &'a InstalledAppId,
+        path: &'a str,
+        query: Option<&'a str>,
+        accepted_encodings: &'a Vec<ContentEncoding>,
+    ) -> BoxFuture<'a, crate::Result<Option<Asset>>>;
+
+    /// How the app's UI needs to be served
+    fn ui_metadata<'a>(
+        &'a self,
+        _app_id: &'a InstalledAppId,
+    ) -> BoxFuture<'a, crate::Result<UiMetadata>> {
+        Box::pin(async { Ok(UiMetadata::default()) })
+    }
+}
+
+/// Resolvers registered for special apps, which take precedence over the UI store
+static ASSET_RESOLVERS: RwLock<BTreeMap<InstalledAppId, Arc<dyn AssetResolver>>> =
+    RwLock::const_new(BTreeMap::new());
+
+/// Serves the UI of the given app with the resolver instead of from the UI store
+pub async fn register_asset_resolver(app_id: InstalledAppId, resolver: Arc<dyn AssetResolver>) {
+    ASSET_RESOLVERS.write().await.insert(app_id, resolver);
+}
+
+pub async fn unregister_asset_resolver(app_id: &InstalledAppId) {
+    ASSET_RESOLVERS.write().await.remove(app_id);
+}
+
+/// Returns the resolver registered for the app, or the one for the UI store if there is none.
+/// Hosts are lowercased by the webviews, so app ids are compared ignoring their case
+pub async fn asset_resolver(fs: &FileSystem, app_id: &InstalledAppId) -> Arc<dyn AssetResolver> {
+    ASSET_RESOLVERS
+        .read()
+        .await
+        .iter()
+        .find(|(registered_app_id, _)| registered_app_id.eq_ignore_ascii_case(app_id))
+        .map(|(_, resolver)| resolver.clone())
+        .unwrap_or_else(|| Arc::new(UiStoreAssetResolver { fs: fs.clone() }))
+}
+
+/// Client for the servers that UIs are proxied to, which can be reached over http or https
+pub(crate) fn https_client() -> Client<HttpsConnector<HttpConnector>> {
+    let connector = HttpsConnectorBuilder::new()
+        .with_webpki_roots()
+        .https_or_http()
+        .enable_http1()
+        .build();
+
+    Client::builder().build(connector)
+}
+
+fn mime_type(path: &str) -> Option<String> {
+    mime_guess::from_path(path)
+        .first()
+        .map(|mime| mime.essence_str().to_string())
+}
+
+fn index_if_root(path: &str) -> &str {
+    match path.is_empty() {
+        true => "index.html",
+        false => path,
+    }
+}
+
+/// Serves the UIs stored in the [`crate::filesystem::UiStore`], preferring their precompressed variants
+pub struct UiStoreAssetResolver {
+    pub fs: FileSystem,
+}
+
+impl AssetResolver for UiStoreAssetResolver {
+    fn resolve_asset<'a>(
+        &'a self,
+        app_id: &'a InstalledAppId,
+        path: &'a str,
+        _query: Option<&'a str>,
+        accepted_encodings: &'a Vec<ContentEncoding>,
+    ) -> BoxFuture<'a, crate::Result<Option<Asset>>> {
+        Box::pin(async move {
+            let ui_store = self.fs.ui_store();
+            let path = index_if_root(path);
+
+            let asset = ui_store
+                .read_ui_file(app_id, path, accepted_encodings)?
+                .map(|(content, content_encoding)| Asset {
+                    content,
+                    mime_type: mime_type(path),
+                    content_encoding,
+                });
+
+            Ok(asset)
+        })
+    }
+
+    fn ui_metadata<'a>(
+        &'a self,
+        app_id: &'a InstalledAppId,
+    ) -> BoxFuture<'a, crate::Result<UiMetadata>> {
+        Box::pin(async move { self.fs.ui_store().ui_metadata(app_id) })
+    }
+}
+
+/// Serves a UI from memory, for apps whose UI is not stored in the filesystem
+pub struct InMemoryAssetResolver {
+    files: HashMap<String, Vec<u8>>,
+    metadata: UiMetadata,
+}
+
+impl InMemoryAssetResolver {
+    pub fn new(files: HashMap<String, Vec<u8>>) -> crate::Result<Self> {
+        let metadata = match files.get(UI_METADATA_FILE) {
+            Some(content) => serde_json::from_slice(content.as_slice())?,
+            None => UiMetadata::default(),
+        };
+
+        Ok(InMemoryAssetResolver { files, metadata })
+    }
+
+    pub fn from_ui_zip(bytes: Vec<u8>) -> crate::Result<Self> {
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))
+            .map_err(|err| FileSystemError::ZipError(err))?;
+
+        let mut files = HashMap::new();
+        for i in 0..archive.len() {
+            let mut file = archive
+                .by_index(i)
+                .map_err(|err| FileSystemError::ZipError(err))?;
+            if file.is_dir() {
+                continue;
+            }
+            let mut content = Vec::with_capacity(file.size() as usize);
+            file.read_to_end(&mut content)?;
+            files.insert(file.name().to_string(), content);
+        }
+
+        Self::new(files)
+    }
+
+    pub async fn from_web_app_bundle(web_app: &WebAppBundle) -> crate::Result<Self> {
+        let ui_bytes = web_app.web_ui_zip_bytes().await?;
+        Self::from_ui_zip(ui_bytes.into_owned().into_inner())
+    }
+}
+
+impl AssetResolver for InMemoryAssetResolver {
+    fn resolve_asset<'a>(
+        &'a self,
+        _app_id: &'a InstalledAppId,
+        path: &'a str,
+        _query: Option<&'a str>,
+        _accepted_encodings: &'a Vec<ContentEncoding>,
+    ) -> BoxFuture<'a, crate::Result<Option<Asset>>> {
+        Box::pin(async move {
+            let path = index_if_root(path);
+
+            let asset = self.files.get(path).map(|content| Asset {
+                content: content.clone(),
+                mime_type: mime_type(path),
+                content_encoding: None,
+            });
+
+            Ok(asset)
+        })
+    }
+
+    fn ui_metadata<'a>(
+        &'a self,
+        _app_id: &'a InstalledAppId,
+    ) -> BoxFuture<'a, crate::Result<UiMetadata>> {
+        Box::pin(async move { Ok(self.metadata.clone()) })
+    }
+}
+
+/// Serves the UI by forwarding the requests to an external http server
+pub struct ProxyAssetResolver {
+    base_url: url::Url,
+    metadata: UiMetadata,
+    client: Client<HttpsConnector<HttpConnector>>,
+}
+
+impl ProxyAssetResolver {
+    pub fn new(base_url: url::Url) -> Self {
+        ProxyAssetResolver {
+            base_url,
+            metadata: UiMetadata::default(),
+            client: https_client(),
+        }
+    }
+
+    /// Sets the metadata of the proxied UI, which can't be read from the external server
+    pub fn with_ui_metadata(mut self, metadata: UiMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// The url of the asset under the base url, with its decoded path encoded again segment by segment
+    fn asset_url(&self, path: &str, query: Option<&str>) -> crate::Result<url::Url> {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .map_err(|_| {
+                crate::Error::HttpServerError(format!("Invalid proxy url {}", self.base_url))
+            })?
+            .pop_if_empty()
+            .extend(path.split('/'));
+        url.set_query(query);
+
+        Ok(url)
+    }
+}
+
+impl AssetResolver for ProxyAssetResolver {
+    fn resolve_asset<'a>(
+        &'a self,
+        _app_id: &'a InstalledAppId,
+        path: &'a str,
+        query: Option<&'a str>,
+        _accepted_encodings: &'a Vec<ContentEncoding>,
+    ) -> BoxFuture<'a, crate::Result<Option<Asset>>> {
+        Box::pin(async move {
+            let url = self.asset_url(path, query)?;
+            let uri: hyper::Uri = url.as_str().parse().map_err(|err| {
+                crate::Error::HttpServerError(format!("Invalid proxy url {url}: {err:?}"))
+            })?;
+
+            let response = self.client.get(uri).await.map_err(|err| {
+                crate::Error::HttpServerError(format!("Failed to proxy {url}: {err:?}"))
+            })?;
+
+            if response.status().eq(&StatusCode::NOT_FOUND) {
+                return Ok(None);
+            }
+            if !response.status().is_success() {
+                return Err(crate::Error::HttpServerError(format!(
+                    "Proxied request to {url} failed with status {}",
+                    response.status()
+                )));
+            }
+
+            let mime_type = response
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|content_type| content_type.to_str().ok())
+                .and_then(|content_type| content_type.split(';').next())
+                .map(|mime_type| mime_type.trim().to_string())
+                .or(mime_type(path));
+
+            let content = hyper::body::to_bytes(response.into_body())
+                .await
+                .map_err(|err| {
+                    crate::Error::HttpServerError(format!("Failed to read {url}: {err:?}"))
+                })?;
+
+            Ok(Some(Asset {
+                content: content.to_vec(),
+                mime_type,
+                content_encoding: None,
+            }))
+        })
+    }
+
+    fn ui_metadata<'a>(
+        &'a self,
+        _app_id: &'a InstalledAppId,
+    ) -> BoxFuture<'a, crate::Result<UiMetadata>> {
+        Box::pin(async move { Ok(self.metadata.clone()) })
+    }
+}
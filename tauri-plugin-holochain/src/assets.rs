@@ -0,0 +1,449 @@
+use std::{collections::BTreeMap, ops::Range};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hyper::{
+    header::{
+        ACCEPT, ACCEPT_ENCODING, ACCEPT_RANGES, CACHE_CONTROL, CONTENT_ENCODING, CONTENT_LENGTH,
+        CONTENT_RANGE, CONTENT_SECURITY_POLICY, CONTENT_TYPE, ETAG, IF_NONE_MATCH, IF_RANGE, RANGE,
+        VARY,
+    },
+    http::{response, HeaderMap, Method, Request, Response},
+    StatusCode,
+};
+
+const SEC_FETCH_MODE: &str = "sec-fetch-mode";
+
+/// An asset of an app UI, ready to be served by the http server or the `happ` protocol
+pub struct Asset {
+    pub content: Vec<u8>,
+    pub mime_type: Option<String>,
+    /// Set if the content is the precompressed variant of the asset
+    pub content_encoding: Option<ContentEncoding>,
+}
+
+/// The encodings in which text assets are precompressed when the UI is stored
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContentEncoding {
+    Brotli,
+    Gzip,
+}
+
+impl ContentEncoding {
+    /// Extension of the precompressed sibling of the asset file
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ContentEncoding::Brotli => "br",
+            ContentEncoding::Gzip => "gz",
+        }
+    }
+
+    fn header_value(&self) -> &'static str {
+        match self {
+            ContentEncoding::Brotli => "br",
+            ContentEncoding::Gzip => "gzip",
+        }
+    }
+}
+
+/// Whether the request is the browser navigating to a page, as opposed to fetching a resource for it.
+/// Older webviews don't send `Sec-Fetch-Mode`, but they still ask for html when navigating
+pub fn is_navigation_request(headers: &HeaderMap) -> bool {
+    if let Some(mode) = headers.get(SEC_FETCH_MODE).and_then(|h| h.to_str().ok()) {
+        return mode.trim().eq_ignore_ascii_case("navigate");
+    }
+
+    headers
+        .get(ACCEPT)
+        .and_then(|h| h.to_str().ok())
+        .map(|accept| {
+            accept
+                .split(',')
+                .any(|media_range| media_range.trim().starts_with("text/html"))
+        })
+        .unwrap_or(false)
+}
+
+/// The precompressed encodings that the client accepts, brotli first since it compresses better
+pub fn accepted_encodings(headers: &HeaderMap) -> Vec<ContentEncoding> {
+    let Some(accept_encoding) = headers.get(ACCEPT_ENCODING).and_then(|h| h.to_str().ok()) else {
+        return vec![];
+    };
+
+    let accepted: Vec<&str> = accept_encoding
+        .split(',')
+        .filter_map(|coding| {
+            let mut params = coding.split(';').map(|param| param.trim());
+            let name = params.next()?;
+            let rejected = params.any(|param| {
+                param
+                    .strip_prefix("q=")
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .eq(&Some(0.0))
+            });
+            match rejected {
+                true => None,
+                false => Some(name),
+            }
+        })
+        .collect();
+
+    [ContentEncoding::Brotli, ContentEncoding::Gzip]
+        .into_iter()
+        .filter(|encoding| {
+            accepted
+                .iter()
+                .any(|name| name.eq(&encoding.header_value()) || name.eq(&"*"))
+        })
+        .collect()
+}
+
+impl Asset {
+    /// Strong validator of the contents of the asset
+    pub fn etag(&self) -> String {
+        let hash = holo_hash::encode::blake2b_256(self.content.as_slice());
+        format!("\"{}\"", URL_SAFE_NO_PAD.encode(hash))
+    }
+
+    /// The charset is only declared for textual assets, binary ones must not have it
+    pub fn content_type(&self) -> String {
+        match &self.mime_type {
+            Some(mime_type) if is_text(mime_type) => format!("{mime_type};charset=utf-8"),
+            Some(mime_type) => mime_type.clone(),
+            None => String::from("application/octet-stream"),
+        }
+    }
+}
+
+pub fn is_text(mime_type: &str) -> bool {
+    mime_type.starts_with("text/")
+        || matches!(
+            mime_type,
+            "application/javascript"
+                | "application/json"
+                | "application/manifest+json"
+                | "application/xml"
+                | "image/svg+xml"
+        )
+}
+
+/// Builds the content security policy for an app's UI: by default it can only load its own assets
+/// and connect to the app interface of the conductor, plus the sources that the app declared in its UI metadata
+pub fn content_security_policy(
+    app_port: u16,
+    http_server_port: u16,
+    relaxations: &BTreeMap<String, Vec<String>>,
+) -> String {
+    let mut directives: Vec<(String, Vec<String>)> = vec![
+        ("default-src", vec!["'self'"]),
+        ("script-src", vec!["'self'", "'wasm-unsafe-eval'"]),
+        ("style-src", vec!["'self'", "'unsafe-inline'"]),
+        ("img-src", vec!["'self'", "data:", "blob:"]),
+        ("font-src", vec!["'self'", "data:"]),
+        ("media-src", vec!["'self'", "data:", "blob:"]),
+        ("worker-src", vec!["'self'", "blob:"]),
+        (
+            "connect-src",
+            vec![
+                "'self'".to_string(),
+                format!("ws://localhost:{app_port}"),
+                format!("ws://127.0.0.1:{app_port}"),
+            ],
+        ),
+        ("object-src", vec!["'none'"]),
+        ("base-uri", vec!["'self'"]),
+        (
+            "frame-ancestors",
+            vec![format!("http://localhost:{http_server_port}")],
+        ),
+    ]
+    .into_iter()
+    .map(|(directive, sources)| {
+        (
+            directive.to_string(),
+            sources.into_iter().map(|s| s.to_string()).collect(),
+        )
+    })
+    .collect();
+
+    for (directive, sources) in relaxations {
+        // Apps can't allow other windows to embed them, and can't inject other directives with separators
+        let is_valid = |s: &String| {
+            !s.is_empty()
+                && s.chars()
+                    .all(|c| c.is_ascii_graphic() && c != ';' && c != ',')
+        };
+        if directive.eq("frame-ancestors") || !is_valid(directive) {
+            log::warn!("Ignoring invalid content security policy directive {directive}");
+            continue;
+        }
+        let sources: Vec<String> = sources.iter().filter(|s| is_valid(s)).cloned().collect();
+
+        match directives.iter_mut().find(|(d, _)| d.eq(directive)) {
+            Some((_, existing_sources)) => existing_sources.extend(sources),
+            None => directives.push((directive.clone(), sources)),
+        }
+    }
+
+    directives
+        .into_iter()
+        .map(|(directive, sources)| format!("{directive} {}", sources.join(" ")))
+        .collect::<Vec<String>>()
+        .join("; ")
+}
+
+/// Builds the response for the asset, answering conditional requests with 304
+/// and single range requests with 206.
+/// The UIs get updated in place, so the browser is asked to always revalidate its cached assets
+pub fn asset_response<B>(
+    request: &Request<B>,
+    asset: Asset,
+    content_security_policy: &String,
+) -> Response<Vec<u8>> {
+    let etag = asset.etag();
+    let headers = request.headers();
+
+    let mut builder = response::Builder::new()
+        .header(CONTENT_SECURITY_POLICY, content_security_policy.as_str())
+        .header(ETAG, etag.as_str())
+        .header(CACHE_CONTROL, "no-cache")
+        .header(ACCEPT_RANGES, "bytes")
+        .header(VARY, "Accept-Encoding");
+    if let Some(content_encoding) = asset.content_encoding {
+        builder = builder.header(CONTENT_ENCODING, content_encoding.header_value());
+    }
+
+    if matches_etag(headers, &etag) {
+        return builder
+            .status(StatusCode::NOT_MODIFIED)
+            .body(vec![])
+            .expect("Failed to build not modified response");
+    }
+
+    let builder = builder.header(CONTENT_TYPE, asset.content_type());
+    let length = asset.content.len();
+
+    let range = match (headers.get(RANGE), headers.get(IF_RANGE)) {
+        // The range only applies if the asset has not changed since the client got the rest of it
+        (Some(_), Some(if_range)) if !if_range.as_bytes().eq(etag.as_bytes()) => None,
+        (Some(range), _) => range.to_str().ok().map(|range| parse_range(range, length)),
+        (None, _) => None,
+    };
+
+    let (builder, content) = match range {
+        Some(RangeRequest::Satisfiable(range)) => (
+            builder.status(StatusCode::PARTIAL_CONTENT).header(
+                CONTENT_RANGE,
+                format!("bytes {}-{}/{length}", range.start, range.end - 1),
+            ),
+            asset.content[range].to_vec(),
+        ),
+        Some(RangeRequest::Unsatisfiable) => {
+            return builder
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(CONTENT_RANGE, format!("bytes */{length}"))
+                .body(vec![])
+                .expect("Failed to build range not satisfiable response");
+        }
+        Some(RangeRequest::Unsupported) | None => (builder.status(StatusCode::OK), asset.content),
+    };
+
+    let builder = builder.header(CONTENT_LENGTH, content.len());
+
+    let body = match request.method().eq(&Method::HEAD) {
+        true => vec![],
+        false => content,
+    };
+
+    builder
+        .body(body)
+        .expect("Failed to build response with asset")
+}
+
+pub fn not_found_response() -> Response<Vec<u8>> {
+    response::Builder::new()
+        .status(StatusCode::NOT_FOUND)
+        .body(vec![])
+        .expect("Failed to build not found response")
+}
+
+pub fn error_response(status: StatusCode, message: String) -> Response<Vec<u8>> {
+    response::Builder::new()
+        .status(status)
+        .header(CONTENT_TYPE, "text/plain;charset=utf-8")
+        .body(message.into_bytes())
+        .expect("Failed to build error response")
+}
+
+fn matches_etag(headers: &HeaderMap, etag: &String) -> bool {
+    let Some(if_none_match) = headers.get(IF_NONE_MATCH).and_then(|h| h.to_str().ok()) else {
+        return false;
+    };
+
+    if_none_match.trim().eq("*")
+        || if_none_match
+            .split(',')
+            .map(|tag| tag.trim().trim_start_matches("W/"))
+            .any(|tag| tag.eq(etag))
+}
+
+enum RangeRequest {
+    Satisfiable(Range<usize>),
+    Unsatisfiable,
+    /// Multiple ranges or other units, which get answered with the whole asset
+    Unsupported,
+}
+
+/// Parses a `Range` header with a single byte range, of the form `bytes=start-end`, `bytes=start-` or `bytes=-suffix`
+fn parse_range(header: &str, length: usize) -> RangeRequest {
+    let Some(range) = header.trim().strip_prefix("bytes=") else {
+        return RangeRequest::Unsupported;
+    };
+    if range.contains(',') {
+        return RangeRequest::Unsupported;
+    }
+    let Some((start, end)) = range.trim().split_once('-') else {
+        return RangeRequest::Unsupported;
+    };
+
+    let (start, end) = match (start.parse::<usize>(), end.parse::<usize>()) {
+        (Ok(start), Ok(end)) if start <= end => (start, (end + 1).min(length)),
+        (Ok(start), Err(_)) if end.is_empty() => (start, length),
+        (Err(_), Ok(suffix)) if start.is_empty() && suffix > 0 => {
+            (length.saturating_sub(suffix), length)
+        }
+        _ => return RangeRequest::Unsupported,
+    };
+
+    if start >= length {
+        return RangeRequest::Unsatisfiable;
+    }
+
+    RangeRequest::Satisfiable(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::http::HeaderValue;
+
+    use super::*;
+
+    fn if_none_match(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn etag_matches() {
+        let etag = String::from("\"abc\"");
+
+        assert!(matches_etag(&if_none_match("\"abc\""), &etag));
+        assert!(matches_etag(&if_none_match("W/\"abc\""), &etag));
+        assert!(matches_etag(&if_none_match("\"xyz\", W/\"abc\""), &etag));
+        assert!(matches_etag(&if_none_match(" * "), &etag));
+    }
+
+    #[test]
+    fn etag_does_not_match() {
+        let etag = String::from("\"abc\"");
+
+        assert!(!matches_etag(&HeaderMap::new(), &etag));
+        assert!(!matches_etag(&if_none_match("\"xyz\""), &etag));
+        assert!(!matches_etag(&if_none_match("abc"), &etag));
+    }
+
+    #[test]
+    fn navigation_requests() {
+        let mut headers = HeaderMap::new();
+        headers.insert(SEC_FETCH_MODE, HeaderValue::from_static("navigate"));
+        headers.insert(ACCEPT, HeaderValue::from_static("*/*"));
+        assert!(is_navigation_request(&headers));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            ACCEPT,
+            HeaderValue::from_static("text/html,application/xhtml+xml;q=0.9,*/*;q=0.8"),
+        );
+        assert!(is_navigation_request(&headers));
+    }
+
+    #[test]
+    fn resource_requests() {
+        let mut headers = HeaderMap::new();
+        headers.insert(SEC_FETCH_MODE, HeaderValue::from_static("cors"));
+        headers.insert(ACCEPT, HeaderValue::from_static("text/html"));
+        assert!(!is_navigation_request(&headers));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_static("*/*"));
+        assert!(!is_navigation_request(&headers));
+        assert!(!is_navigation_request(&HeaderMap::new()));
+    }
+
+    #[test]
+    fn closed_range() {
+        assert!(
+            matches!(parse_range("bytes=0-9", 100), RangeRequest::Satisfiable(r) if r == (0..10))
+        );
+        assert!(
+            matches!(parse_range("bytes=90-200", 100), RangeRequest::Satisfiable(r) if r == (90..100))
+        );
+    }
+
+    #[test]
+    fn open_ended_range() {
+        assert!(
+            matches!(parse_range("bytes=10-", 100), RangeRequest::Satisfiable(r) if r == (10..100))
+        );
+    }
+
+    #[test]
+    fn suffix_range() {
+        assert!(
+            matches!(parse_range("bytes=-10", 100), RangeRequest::Satisfiable(r) if r == (90..100))
+        );
+        assert!(
+            matches!(parse_range("bytes=-200", 100), RangeRequest::Satisfiable(r) if r == (0..100))
+        );
+        assert!(matches!(
+            parse_range("bytes=-0", 100),
+            RangeRequest::Unsupported
+        ));
+    }
+
+    #[test]
+    fn unsatisfiable_range() {
+        assert!(matches!(
+            parse_range("bytes=100-", 100),
+            RangeRequest::Unsatisfiable
+        ));
+        assert!(matches!(
+            parse_range("bytes=150-200", 100),
+            RangeRequest::Unsatisfiable
+        ));
+        assert!(matches!(
+            parse_range("bytes=-10", 0),
+            RangeRequest::Unsatisfiable
+        ));
+    }
+
+    #[test]
+    fn unsupported_range() {
+        assert!(matches!(
+            parse_range("bytes=0-9,20-29", 100),
+            RangeRequest::Unsupported
+        ));
+        assert!(matches!(
+            parse_range("items=0-9", 100),
+            RangeRequest::Unsupported
+        ));
+        assert!(matches!(
+            parse_range("bytes=9-0", 100),
+            RangeRequest::Unsupported
+        ));
+        assert!(matches!(
+            parse_range("bytes=a-b", 100),
+            RangeRequest::Unsupported
+        ));
+    }
+}
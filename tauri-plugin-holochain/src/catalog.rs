@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use holochain::prelude::{AppManifest, EntryHashB64, Timestamp};
+use holochain_client::{AppInfo, InstalledAppId};
+use holochain_types::web_app::WebAppBundle;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    filesystem::FileSystem,
+    locales::{negotiate_locale, preferred_locales},
+};
+
+/// Where an installed app came from
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum AppSource {
+    /// Installed from a bundle handed to the launcher
+    Bundle,
+    /// Installed by accepting an invite to join the network of another agent's app
+    Invite,
+    /// Installed as the new version of the given app, which handed its data over to it
+    Migration(InstalledAppId),
+}
+
+/// What the launcher shows about an installed app
+#[derive(Serialize, Debug, Clone)]
+pub struct AppCatalogEntry {
+    pub app_id: InstalledAppId,
+    /// The title declared in the UI metadata in the preferred locale,
+    /// or the name from the manifest, or the app id if it has neither
+    pub name: String,
+    pub description: Option<String>,
+    /// The icon as a data url, ready to be used as the `src` of an image
+    pub icon: Option<String>,
+    pub version: Option<String>,
+    /// Hash of the package in the [`crate::filesystem::WebAppStore`], the web-app or, for apps without UI, the app bundle
+    pub bundle_hash: Option<EntryHashB64>,
+    pub installed_at: Option<Timestamp>,
+    pub source: Option<AppSource>,
+    pub app_info: AppInfo,
+}
+
+/// The name and description of an app as declared in the manifests of its bundle
+pub struct ManifestInfo {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+impl ManifestInfo {
+    pub fn from_app_manifest(manifest: &AppManifest) -> Self {
+        let AppManifest::V1(manifest) = manifest;
+
+        ManifestInfo {
+            name: manifest.name.clone(),
+            description: manifest.description.clone(),
+        }
+    }
+
+    /// The name comes from the web-app manifest, since the happ one often only names the backend
+    pub async fn from_web_app_bundle(web_app: &WebAppBundle) -> crate::Result<Self> {
+        let happ_bundle = web_app.happ_bundle().await?;
+
+        Ok(ManifestInfo {
+            name: web_app.manifest().app_name().to_string(),
+            ..Self::from_app_manifest(happ_bundle.manifest())
+        })
+    }
+}
+
+/// Records the catalog information of the app in its artifacts, to be called every time it's installed or updated.
+/// The source is only given on install, when the install date is recorded with it.
+/// The icon declared in the UI metadata is read from the stored UI, so the UI must have been stored already
+pub fn record_app_catalog(
+    fs: &FileSystem,
+    app_id: &InstalledAppId,
+    manifest_info: ManifestInfo,
+    source: Option<AppSource>,
+) -> crate::Result<()> {
+    let mut artifacts = fs
+        .installed_apps_store()
+        .get_artifacts(app_id)?
+        .unwrap_or_default();
+
+    artifacts.name = Some(manifest_info.name);
+    artifacts.description = manifest_info.description;
+    if let Some(source) = source {
+        artifacts.installed_at = Some(Timestamp::now());
+        artifacts.source = Some(source);
+    }
+
+    store_ui_icon(fs, app_id)?;
+
+    fs.installed_apps_store()
+        .store_artifacts(app_id, &artifacts)?;
+
+    Ok(())
+}
+
+/// Stores the icon declared in the UI metadata of the app in the [`crate::filesystem::IconStore`] as a data url,
+/// or removes the one of its previous version if it doesn't declare any anymore
+fn store_ui_icon(fs: &FileSystem, app_id: &InstalledAppId) -> crate::Result<()> {
+    let ui_store = fs.ui_store();
+    let icon_path = match ui_store.has_ui(app_id) {
+        true => ui_store.ui_metadata(app_id)?.icon,
+        false => None,
+    };
+    let Some(icon_path) = icon_path else {
+        return fs.icon_store().remove_app_icon(app_id);
+    };
+    let Some((content, _)) = ui_store.read_ui_file(app_id, icon_path.as_str(), &vec![])? else {
+        log::warn!("The icon {icon_path} declared by app {app_id} is not in its UI");
+        return fs.icon_store().remove_app_icon(app_id);
+    };
+
+    let mime_type = mime_guess::from_path(&icon_path)
+        .first()
+        .map(|mime| mime.essence_str().to_string())
+        .unwrap_or(String::from("image/png"));
+    let icon_src = format!("data:{mime_type};base64,{}", STANDARD.encode(content));
+
+    fs.icon_store().store_app_icon(app_id, icon_src)
+}
+
+/// Builds the catalog entry for the installed app from its artifacts and the metadata of its stored UI,
+/// localized to the preferred locales
+pub fn app_catalog_entry(
+    fs: &FileSystem,
+    app_info: AppInfo,
+    preferred_locales: &Vec<String>,
+) -> crate::Result<AppCatalogEntry> {
+    let app_id = app_info.installed_app_id.clone();
+    let artifacts = fs
+        .installed_apps_store()
+        .get_artifacts(&app_id)?
+        .unwrap_or_default();
+    let ui_metadata = match fs.ui_store().has_ui(&app_id) {
+        true => fs.ui_store().ui_metadata(&app_id)?,
+        false => Default::default(),
+    };
+
+    let icon = fs.icon_store().get_app_icon(&app_id)?;
+
+    Ok(AppCatalogEntry {
+        name: localized(&ui_metadata.title, preferred_locales)
+            .or(artifacts.name)
+            .unwrap_or(app_id.clone()),
+        description: localized(&ui_metadata.description, preferred_locales)
+            .or(artifacts.description),
+        icon,
+        version: ui_metadata.version,
+        bundle_hash: artifacts.web_app_hash.or(artifacts.app_bundle_hash),
+        installed_at: artifacts.installed_at,
+        source: artifacts.source,
+        app_id,
+        app_info,
+    })
+}
+
+/// Builds the catalog entries of all the given apps, in the locales that the user prefers
+pub fn app_catalog(fs: &FileSystem, apps: Vec<AppInfo>) -> crate::Result<Vec<AppCatalogEntry>> {
+    let preferred_locales = preferred_locales(fs)?;
+
+    apps.into_iter()
+        .map(|app_info| app_catalog_entry(fs, app_info, &preferred_locales))
+        .collect()
+}
+
+fn localized(texts: &BTreeMap<String, String>, preferred_locales: &Vec<String>) -> Option<String> {
+    let locales: Vec<String> = texts.keys().cloned().collect();
+
+    negotiate_locale(preferred_locales, &locales).and_then(|locale| texts.get(&locale).cloned())
+}
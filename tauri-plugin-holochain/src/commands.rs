@@ -1,6 +1,11 @@
+pub mod clone_cells;
 pub mod get_locales;
 pub mod get_runtime_info;
 pub mod install_web_app;
+pub mod invites;
 pub mod list_apps;
+pub mod migrate_app;
 pub mod open_app;
+pub mod provide_membrane_proofs;
+pub mod rollback_app;
 pub mod sign_zome_call;
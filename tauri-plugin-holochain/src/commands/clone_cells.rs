@@ -0,0 +1,109 @@
+use holochain::prelude::{
+    CloneCellId, CreateCloneCellPayload, DeleteCloneCellPayload, DisableCloneCellPayload,
+    DnaModifiersOpt, EnableCloneCellPayload, MembraneProof, NetworkSeed, RoleName, YamlProperties,
+};
+use holochain_conductor_api::CellInfo;
+use tauri::{command, AppHandle, Runtime, Window};
+
+use crate::HolochainExt;
+
+/// Clone cells can only be managed by the windows of the app they belong to
+#[command]
+pub(crate) async fn create_clone_cell<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    role_name: RoleName,
+    network_seed: Option<NetworkSeed>,
+    properties: Option<YamlProperties>,
+    name: Option<String>,
+    membrane_proof: Option<MembraneProof>,
+) -> crate::Result<CellInfo> {
+    let app_id = app.holochain()?.window_app_id(&window)?;
+
+    let mut modifiers = DnaModifiersOpt::none();
+    if let Some(network_seed) = network_seed {
+        modifiers = modifiers.with_network_seed(network_seed);
+    }
+    if let Some(properties) = properties {
+        modifiers = modifiers.with_properties(properties);
+    }
+
+    let mut app_ws = app.holochain()?.app_websocket().await?;
+
+    let cloned_cell = app_ws
+        .create_clone_cell(CreateCloneCellPayload {
+            app_id,
+            role_name,
+            modifiers,
+            membrane_proof,
+            name,
+        })
+        .await
+        .map_err(|err| crate::Error::ConductorApiError(err))?;
+
+    Ok(CellInfo::Cloned(cloned_cell))
+}
+
+#[command]
+pub(crate) async fn enable_clone_cell<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    clone_cell_id: CloneCellId,
+) -> crate::Result<CellInfo> {
+    let app_id = app.holochain()?.window_app_id(&window)?;
+
+    let mut app_ws = app.holochain()?.app_websocket().await?;
+
+    let cloned_cell = app_ws
+        .enable_clone_cell(EnableCloneCellPayload {
+            app_id,
+            clone_cell_id,
+        })
+        .await
+        .map_err(|err| crate::Error::ConductorApiError(err))?;
+
+    Ok(CellInfo::Cloned(cloned_cell))
+}
+
+#[command]
+pub(crate) async fn disable_clone_cell<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    clone_cell_id: CloneCellId,
+) -> crate::Result<()> {
+    let app_id = app.holochain()?.window_app_id(&window)?;
+
+    let mut app_ws = app.holochain()?.app_websocket().await?;
+
+    app_ws
+        .disable_clone_cell(DisableCloneCellPayload {
+            app_id,
+            clone_cell_id,
+        })
+        .await
+        .map_err(|err| crate::Error::ConductorApiError(err))?;
+
+    Ok(())
+}
+
+/// Deletes a clone cell, which needs to have been disabled before
+#[command]
+pub(crate) async fn delete_clone_cell<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    clone_cell_id: CloneCellId,
+) -> crate::Result<()> {
+    let app_id = app.holochain()?.window_app_id(&window)?;
+
+    let mut admin_ws = app.holochain()?.admin_websocket().await?;
+
+    admin_ws
+        .delete_clone_cell(DeleteCloneCellPayload {
+            app_id,
+            clone_cell_id,
+        })
+        .await
+        .map_err(|err| crate::Error::ConductorApiError(err))?;
+
+    Ok(())
+}
@@ -1,7 +1,26 @@
 use sys_locale::get_locales as get_locales_native;
-use tauri::command;
+use tauri::{command, AppHandle, Runtime, Window};
 
+use crate::{preferred_locales, HolochainExt};
+
+/// The locales of the user by order of preference, only the ones of the system until holochain is ready
 #[command]
-pub(crate) fn get_locales() -> Vec<String> {
-    get_locales_native().collect()
+pub(crate) fn get_locales<R: Runtime>(app_handle: AppHandle<R>) -> crate::Result<Vec<String>> {
+    match app_handle.holochain() {
+        Ok(holochain) => preferred_locales(&holochain.filesystem),
+        Err(_) => Ok(get_locales_native().collect()),
+    }
+}
+
+/// Only the launcher can change the preferred locale, app windows can't change it for the other apps
+#[command]
+pub(crate) fn set_preferred_locale<R: Runtime>(
+    app_handle: AppHandle<R>,
+    window: Window<R>,
+    locale: Option<String>,
+) -> crate::Result<()> {
+    let holochain = app_handle.holochain()?;
+    holochain.authorize_launcher_window(&window)?;
+
+    holochain.set_preferred_locale(locale)
 }
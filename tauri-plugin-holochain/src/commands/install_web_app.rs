@@ -1,13 +1,14 @@
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
+    io::Read,
     path::PathBuf,
 };
 
 use holochain::prelude::{
-    AppBundle, AppBundleError, AppBundleSource, AppManifest, CoordinatorBundle,
-    CoordinatorManifest, DnaBundle, DnaError, DnaFile, DnaHash, MembraneProof, NetworkSeed,
-    RoleName, UpdateCoordinatorsPayload, ZomeDependency, ZomeError, ZomeLocation, ZomeManifest,
-    ZomeName,
+    AgentPubKey, AppBundle, AppBundleError, AppBundleSource, AppManifest, CoordinatorBundle,
+    CoordinatorManifest, DnaBundle, DnaDef, DnaError, DnaFile, DnaHash, EntryHash, EntryHashB64,
+    MembraneProof, NetworkSeed, RoleName, UpdateCoordinatorsPayload, ZomeDependency, ZomeError,
+    ZomeLocation, ZomeManifest, ZomeName,
 };
 use holochain_client::{
     AdminWebsocket, AppInfo, ConductorApiError, InstallAppPayload, InstalledAppId,
@@ -15,8 +16,13 @@ use holochain_client::{
 use holochain_conductor_api::{AppInfoStatus, CellInfo};
 use holochain_types::web_app::WebAppBundle;
 use mr_bundle::{error::MrBundleError, Bundle, ResourceBytes};
+use serde::Serialize;
 
-use crate::filesystem::{FileSystem, FileSystemError};
+use crate::{
+    catalog::{record_app_catalog, AppSource, ManifestInfo},
+    filesystem::{app_bundle_hash, web_app_hash, FileSystem, FileSystemError},
+    hrl_resolvers::{forget_declared_hrl_resolvers, forget_hrl_resolvers},
+};
 
 pub async fn install_web_app(
     admin_ws: &mut AdminWebsocket,
@@ -25,18 +31,429 @@ pub async fn install_web_app(
     bundle: WebAppBundle,
     membrane_proofs: HashMap<RoleName, MembraneProof>,
     network_seed: Option<NetworkSeed>,
+    source: AppSource,
 ) -> crate::Result<AppInfo> {
     let app_info = install_app(
         admin_ws,
         app_id.clone(),
         bundle.happ_bundle().await?,
         membrane_proofs,
-        network_seed,
+        network_seed.clone(),
     )
     .await?;
 
     fs.ui_store().extract_and_store_ui(&app_id, &bundle).await?;
     log::info!("Installed web-app's ui {app_id:?}");
+    log_content_security_policy_relaxations(fs, &app_id)?;
+
+    store_web_app_package(fs, &app_id, &bundle).await?;
+    store_network_seed(fs, &app_id, network_seed)?;
+    record_app_catalog(
+        fs,
+        &app_id,
+        ManifestInfo::from_web_app_bundle(&bundle).await?,
+        Some(source),
+    )?;
+
+    Ok(app_info)
+}
+
+fn log_content_security_policy_relaxations(
+    fs: &FileSystem,
+    app_id: &InstalledAppId,
+) -> crate::Result<()> {
+    let metadata = fs.ui_store().ui_metadata(app_id)?;
+
+    for (directive, sources) in metadata.content_security_policy {
+        log::warn!("App {app_id} relaxes its content security policy: {directive} {sources:?}");
+    }
+
+    Ok(())
+}
+
+fn store_network_seed(
+    fs: &FileSystem,
+    app_id: &InstalledAppId,
+    network_seed: Option<NetworkSeed>,
+) -> crate::Result<()> {
+    let mut artifacts = fs
+        .installed_apps_store()
+        .get_artifacts(app_id)?
+        .unwrap_or_default();
+
+    artifacts.network_seed = network_seed;
+
+    fs.installed_apps_store()
+        .store_artifacts(app_id, &artifacts)?;
+
+    Ok(())
+}
+
+/// Caches the web-app package in the [`crate::filesystem::WebAppStore`],
+/// and records it as an artifact of the installed app.
+/// The package it replaces is kept as the previous version to roll back to, and the one before it is removed
+pub async fn store_web_app_package(
+    fs: &FileSystem,
+    app_id: &InstalledAppId,
+    bundle: &WebAppBundle,
+) -> crate::Result<()> {
+    let hash = web_app_hash(bundle)?;
+    fs.webapp_store().store_webapp(&hash, bundle).await?;
+
+    let mut artifacts = fs
+        .installed_apps_store()
+        .get_artifacts(app_id)?
+        .unwrap_or_default();
+
+    let discarded_hash = replace_package(
+        &mut artifacts.web_app_hash,
+        &mut artifacts.previous_web_app_hash,
+        hash.into(),
+    );
+
+    fs.installed_apps_store()
+        .store_artifacts(app_id, &artifacts)?;
+
+    if let Some(discarded_hash) = discarded_hash {
+        remove_unused_package(fs, &discarded_hash, app_id)?;
+    }
+
+    Ok(())
+}
+
+/// Caches the bundle of an app installed without UI like [`store_web_app_package`] does for web-apps,
+/// so that it can be rolled back too
+pub async fn store_app_bundle_package(
+    fs: &FileSystem,
+    app_id: &InstalledAppId,
+    bundle: &AppBundle,
+) -> crate::Result<()> {
+    let hash = app_bundle_hash(bundle)?;
+    fs.webapp_store().store_app_bundle(&hash, bundle).await?;
+
+    let mut artifacts = fs
+        .installed_apps_store()
+        .get_artifacts(app_id)?
+        .unwrap_or_default();
+
+    let discarded_hash = replace_package(
+        &mut artifacts.app_bundle_hash,
+        &mut artifacts.previous_app_bundle_hash,
+        hash.into(),
+    );
+
+    fs.installed_apps_store()
+        .store_artifacts(app_id, &artifacts)?;
+
+    if let Some(discarded_hash) = discarded_hash {
+        remove_unused_package(fs, &discarded_hash, app_id)?;
+    }
+
+    Ok(())
+}
+
+/// Makes the package the current one, keeping the one it replaces as the previous version.
+/// Returns the package that was the previous version until now, if the app doesn't keep it anymore
+fn replace_package(
+    current_hash: &mut Option<EntryHashB64>,
+    previous_hash: &mut Option<EntryHashB64>,
+    hash: EntryHashB64,
+) -> Option<EntryHashB64> {
+    if current_hash.as_ref().eq(&Some(&hash)) {
+        return None;
+    }
+
+    let discarded_hash = match current_hash.replace(hash.clone()) {
+        Some(replaced_hash) => previous_hash.replace(replaced_hash),
+        None => None,
+    };
+
+    discarded_hash.filter(|discarded_hash| {
+        !discarded_hash.eq(&hash) && !previous_hash.as_ref().eq(&Some(discarded_hash))
+    })
+}
+
+/// Updates the catalog information of the app with the web-app it was updated to
+async fn record_web_app_catalog(
+    fs: &FileSystem,
+    app_id: &InstalledAppId,
+    bundle: &WebAppBundle,
+) -> Result<(), UpdateAppError> {
+    let manifest_info = ManifestInfo::from_web_app_bundle(bundle)
+        .await
+        .map_err(|err| UpdateAppError::ReadWebAppError(format!("{err:?}")))?;
+
+    record_app_catalog(fs, app_id, manifest_info, None)
+        .map_err(|err| UpdateAppError::StoreWebAppError(format!("{err:?}")))
+}
+
+/// Restores the coordinator zomes and the UI of the web-app package that was installed before the last update.
+/// The two versions are swapped, so rolling back again goes back to the version that was rolled back
+pub async fn rollback_web_app(
+    admin_ws: &mut AdminWebsocket,
+    fs: &FileSystem,
+    app_id: InstalledAppId,
+) -> Result<(), UpdateAppError> {
+    let mut artifacts = fs
+        .installed_apps_store()
+        .get_artifacts(&app_id)
+        .map_err(|err| UpdateAppError::ReadWebAppError(format!("{err:?}")))?
+        .unwrap_or_default();
+
+    let previous_hash = artifacts
+        .previous_web_app_hash
+        .clone()
+        .ok_or(UpdateAppError::NoPreviousVersion(app_id.clone()))?;
+    let previous_web_app = fs
+        .webapp_store()
+        .get_webapp(&previous_hash.clone().into())
+        .map_err(|err| UpdateAppError::ReadWebAppError(format!("{err:?}")))?
+        .ok_or(UpdateAppError::NoPreviousVersion(app_id.clone()))?;
+
+    log::info!("Rolling back app {app_id} to its previous version");
+
+    // The previous version passed the integrity check when it was updated from, so only its coordinators get restored
+    restore_coordinators(admin_ws, &app_id, previous_web_app.happ_bundle().await?).await?;
+
+    fs.ui_store()
+        .extract_and_store_ui(&app_id, &previous_web_app)
+        .await?;
+    forget_declared_hrl_resolvers(&app_id).await;
+
+    artifacts.previous_web_app_hash = artifacts.web_app_hash.replace(previous_hash);
+    fs.installed_apps_store()
+        .store_artifacts(&app_id, &artifacts)
+        .map_err(|err| UpdateAppError::StoreWebAppError(format!("{err:?}")))?;
+    record_web_app_catalog(fs, &app_id, &previous_web_app).await?;
+
+    log::info!("Rolled back app {app_id}");
+
+    Ok(())
+}
+
+/// Restores the coordinator zomes of the app bundle that was installed before the last update of the app,
+/// like [`rollback_web_app`] does for web-apps
+pub async fn rollback_app_bundle(
+    admin_ws: &mut AdminWebsocket,
+    fs: &FileSystem,
+    app_id: InstalledAppId,
+) -> Result<(), UpdateAppError> {
+    let mut artifacts = fs
+        .installed_apps_store()
+        .get_artifacts(&app_id)
+        .map_err(|err| UpdateAppError::ReadWebAppError(format!("{err:?}")))?
+        .unwrap_or_default();
+
+    let previous_hash = artifacts
+        .previous_app_bundle_hash
+        .clone()
+        .ok_or(UpdateAppError::NoPreviousVersion(app_id.clone()))?;
+    let previous_app_bundle = fs
+        .webapp_store()
+        .get_app_bundle(&previous_hash.clone().into())
+        .map_err(|err| UpdateAppError::ReadWebAppError(format!("{err:?}")))?
+        .ok_or(UpdateAppError::NoPreviousVersion(app_id.clone()))?;
+
+    log::info!("Rolling back app {app_id} to its previous version");
+
+    let manifest_info = ManifestInfo::from_app_manifest(previous_app_bundle.manifest());
+    restore_coordinators(admin_ws, &app_id, previous_app_bundle).await?;
+
+    artifacts.previous_app_bundle_hash = artifacts.app_bundle_hash.replace(previous_hash);
+    fs.installed_apps_store()
+        .store_artifacts(&app_id, &artifacts)
+        .map_err(|err| UpdateAppError::StoreWebAppError(format!("{err:?}")))?;
+    record_app_catalog(fs, &app_id, manifest_info, None)
+        .map_err(|err| UpdateAppError::StoreWebAppError(format!("{err:?}")))?;
+
+    log::info!("Rolled back app {app_id}");
+
+    Ok(())
+}
+
+/// Makes the coordinator zomes of the app the ones of the given bundle.
+/// The conductor can only add or replace coordinator zomes, so if the app has any that the bundle doesn't,
+/// nothing is changed, instead of leaving them next to the restored ones
+async fn restore_coordinators(
+    admin_ws: &mut AdminWebsocket,
+    app_id: &InstalledAppId,
+    bundle: AppBundle,
+) -> Result<(), UpdateAppError> {
+    let app = get_app(admin_ws, app_id).await?;
+
+    let mut extra_zomes: BTreeSet<ZomeName> = BTreeSet::new();
+    for (_role_name, dna_hash, dna_file) in
+        app_cells(&app, resolve_dna_files(bundle.clone()).await?)?
+    {
+        let installed_dna_def = admin_ws
+            .get_dna_definition(dna_hash)
+            .await
+            .map_err(|err| UpdateAppError::ConductorApiError(err))?;
+
+        for (zome_name, _) in installed_dna_def.coordinator_zomes.iter() {
+            if !dna_file
+                .dna_def()
+                .coordinator_zomes
+                .iter()
+                .any(|(name, _)| name.eq(zome_name))
+            {
+                extra_zomes.insert(zome_name.clone());
+            }
+        }
+    }
+    if !extra_zomes.is_empty() {
+        return Err(UpdateAppError::CoordinatorZomesNotRemovable(
+            app_id.clone(),
+            extra_zomes.into_iter().collect(),
+        ));
+    }
+
+    update_app(admin_ws, app_id.clone(), bundle).await
+}
+
+pub async fn uninstall_app(
+    admin_ws: &mut AdminWebsocket,
+    fs: &FileSystem,
+    app_id: String,
+) -> crate::Result<()> {
+    log::info!("Uninstalling app {}", app_id);
+
+    admin_ws
+        .uninstall_app(app_id.clone())
+        .await
+        .map_err(|err| crate::Error::ConductorApiError(err))?;
+
+    forget_hrl_resolvers(&app_id).await;
+    fs.ui_store().remove_ui(&app_id)?;
+
+    if let Some(artifacts) = fs.installed_apps_store().get_artifacts(&app_id)? {
+        for hash in artifacts.package_hashes() {
+            remove_unused_package(fs, &hash, &app_id)?;
+        }
+        fs.installed_apps_store().remove_artifacts(&app_id)?;
+    }
+    fs.icon_store().remove_app_icon(&app_id)?;
+    fs.window_state_store().remove_window_state(&app_id)?;
+    fs.window_state_store().set_app_open(&app_id, false)?;
+
+    log::info!("Uninstalled app {app_id:?}");
+
+    Ok(())
+}
+
+/// Removes the package, web-app or app bundle, from the [`crate::filesystem::WebAppStore`] unless other apps still use it
+fn remove_unused_package(
+    fs: &FileSystem,
+    package_hash: &EntryHashB64,
+    app_id: &InstalledAppId,
+) -> crate::Result<()> {
+    if fs
+        .installed_apps_store()
+        .is_package_used_by_others(package_hash, app_id)?
+    {
+        log::info!("Keeping package {package_hash}, which other apps still use");
+        return Ok(());
+    }
+
+    fs.webapp_store()
+        .remove_webapp(&package_hash.clone().into())
+}
+
+/// An installation that is waiting for its UI to provide the membrane proofs
+#[derive(Clone)]
+pub struct PendingInstallation {
+    pub agent_key: AgentPubKey,
+    pub web_app_bundle: WebAppBundle,
+    pub network_seed: Option<NetworkSeed>,
+}
+
+/// Pending installations only live in memory, so the UIs that were stored for the ones that
+/// were never completed are left behind without an app when the launcher stops: removes them
+pub async fn remove_abandoned_uis(
+    admin_ws: &mut AdminWebsocket,
+    fs: &FileSystem,
+) -> crate::Result<()> {
+    let installed_app_ids: BTreeSet<InstalledAppId> = admin_ws
+        .list_apps(None)
+        .await
+        .map_err(|err| crate::Error::ConductorApiError(err))?
+        .into_iter()
+        .map(|app_info| app_info.installed_app_id)
+        .collect();
+
+    for app_id in fs.ui_store().list_uis()? {
+        if installed_app_ids.contains(&app_id) {
+            continue;
+        }
+        log::info!("Removing the UI of app {app_id}, whose installation was never completed");
+
+        fs.ui_store().remove_ui(&app_id)?;
+        fs.window_state_store().remove_window_state(&app_id)?;
+        fs.window_state_store().set_app_open(&app_id, false)?;
+    }
+
+    Ok(())
+}
+
+/// Generates the agent key and installs only the UI of the web-app,
+/// so that it can fetch or construct the membrane proofs for that agent key
+pub async fn prepare_web_app_installation(
+    admin_ws: &mut AdminWebsocket,
+    fs: &FileSystem,
+    app_id: String,
+    bundle: WebAppBundle,
+    network_seed: Option<NetworkSeed>,
+) -> crate::Result<PendingInstallation> {
+    log::info!("Preparing the installation of app {}", app_id);
+
+    let agent_key = admin_ws
+        .generate_agent_pub_key()
+        .await
+        .map_err(|err| crate::Error::ConductorApiError(err))?;
+
+    fs.ui_store().extract_and_store_ui(&app_id, &bundle).await?;
+    log::info!("Installed web-app's ui {app_id:?}");
+    log_content_security_policy_relaxations(fs, &app_id)?;
+
+    Ok(PendingInstallation {
+        agent_key,
+        web_app_bundle: bundle,
+        network_seed,
+    })
+}
+
+/// Installs the happ of a pending installation with the membrane proofs provided by its UI
+pub async fn complete_web_app_installation(
+    admin_ws: &mut AdminWebsocket,
+    fs: &FileSystem,
+    app_id: String,
+    pending_installation: PendingInstallation,
+    membrane_proofs: HashMap<RoleName, MembraneProof>,
+) -> crate::Result<AppInfo> {
+    let PendingInstallation {
+        agent_key,
+        web_app_bundle,
+        network_seed,
+    } = pending_installation;
+
+    let app_info = install_app_with_agent_key(
+        admin_ws,
+        app_id.clone(),
+        agent_key,
+        web_app_bundle.happ_bundle().await?,
+        membrane_proofs,
+        network_seed.clone(),
+    )
+    .await?;
+
+    store_web_app_package(fs, &app_id, &web_app_bundle).await?;
+    store_network_seed(fs, &app_id, network_seed)?;
+    record_app_catalog(
+        fs,
+        &app_id,
+        ManifestInfo::from_web_app_bundle(&web_app_bundle).await?,
+        Some(AppSource::Bundle),
+    )?;
 
     Ok(app_info)
 }
@@ -55,6 +472,25 @@ pub async fn install_app(
         .await
         .map_err(|err| crate::Error::ConductorApiError(err))?;
 
+    install_app_with_agent_key(
+        admin_ws,
+        app_id,
+        agent_key,
+        bundle,
+        membrane_proofs,
+        network_seed,
+    )
+    .await
+}
+
+pub async fn install_app_with_agent_key(
+    admin_ws: &mut AdminWebsocket,
+    app_id: String,
+    agent_key: AgentPubKey,
+    bundle: AppBundle,
+    membrane_proofs: HashMap<RoleName, MembraneProof>,
+    network_seed: Option<NetworkSeed>,
+) -> crate::Result<AppInfo> {
     let app_info = admin_ws
         .install_app(InstallAppPayload {
             agent_key,
@@ -87,11 +523,93 @@ pub async fn update_web_app(
 
     fs.ui_store().extract_and_store_ui(&app_id, &bundle).await?;
     log::info!("Updated web-app's ui {app_id:?}");
+    // Declared in the UI metadata, which only changes now
+    forget_declared_hrl_resolvers(&app_id).await;
+
+    store_web_app_package(fs, &app_id, &bundle)
+        .await
+        .map_err(|err| UpdateAppError::StoreWebAppError(format!("{err:?}")))?;
+    record_web_app_catalog(fs, &app_id, &bundle).await?;
 
     // Ok(app_info)
     Ok(())
 }
 
+/// Installs the new version of the web-app as a separate app with the same agent key and network seed as the old one,
+/// for when its integrity zomes have changed. The old app is left untouched, so that its data can be handed over.
+/// If storing the new app fails, it's uninstalled again
+pub async fn migrate_web_app(
+    admin_ws: &mut AdminWebsocket,
+    fs: &FileSystem,
+    app_id: InstalledAppId,
+    new_app_id: InstalledAppId,
+    bundle: WebAppBundle,
+    membrane_proofs: HashMap<RoleName, MembraneProof>,
+) -> crate::Result<AppInfo> {
+    log::info!("Migrating app {app_id} to {new_app_id}");
+
+    let apps = admin_ws
+        .list_apps(None)
+        .await
+        .map_err(|err| crate::Error::ConductorApiError(err))?;
+    let app = apps
+        .into_iter()
+        .find(|app| app.installed_app_id.eq(&app_id))
+        .ok_or(crate::Error::MigrationError(format!(
+            "App {app_id} is not installed"
+        )))?;
+
+    let network_seed = fs
+        .installed_apps_store()
+        .get_artifacts(&app_id)?
+        .and_then(|artifacts| artifacts.network_seed);
+
+    let app_info = install_app_with_agent_key(
+        admin_ws,
+        new_app_id.clone(),
+        app.agent_pub_key,
+        bundle.happ_bundle().await?,
+        membrane_proofs,
+        network_seed.clone(),
+    )
+    .await?;
+
+    let stored = store_migrated_web_app(fs, &app_id, &new_app_id, &bundle, network_seed).await;
+    if let Err(err) = stored {
+        log::error!("Failed to store migrated app {new_app_id}, uninstalling it: {err:?}");
+        if let Err(uninstall_err) = uninstall_app(admin_ws, fs, new_app_id.clone()).await {
+            log::error!("Failed to uninstall app {new_app_id}: {uninstall_err:?}");
+        }
+        return Err(err);
+    }
+
+    Ok(app_info)
+}
+
+async fn store_migrated_web_app(
+    fs: &FileSystem,
+    app_id: &InstalledAppId,
+    new_app_id: &InstalledAppId,
+    bundle: &WebAppBundle,
+    network_seed: Option<NetworkSeed>,
+) -> crate::Result<()> {
+    fs.ui_store()
+        .extract_and_store_ui(new_app_id, bundle)
+        .await?;
+    log_content_security_policy_relaxations(fs, new_app_id)?;
+
+    store_web_app_package(fs, new_app_id, bundle).await?;
+    store_network_seed(fs, new_app_id, network_seed)?;
+    record_app_catalog(
+        fs,
+        new_app_id,
+        ManifestInfo::from_web_app_bundle(bundle).await?,
+        Some(AppSource::Migration(app_id.clone())),
+    )?;
+
+    Ok(())
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum UpdateAppError {
     #[error(transparent)]
@@ -118,11 +636,80 @@ pub enum UpdateAppError {
     #[error("Error connecting to the websocket")]
     WebsocketError,
 
+    #[error("Error storing the web-app package: {0}")]
+    StoreWebAppError(String),
+
+    #[error("Error reading the installed web-app package: {0}")]
+    ReadWebAppError(String),
+
+    #[error("There is no previous version of app {0} to roll back to")]
+    NoPreviousVersion(InstalledAppId),
+
+    #[error("The conductor can't remove coordinator zomes, so app {0} can't go back to a version without {1:?}")]
+    CoordinatorZomesNotRemovable(InstalledAppId, Vec<ZomeName>),
+
     #[error("The given app was not found: {0}")]
     AppNotFound(String),
 
     #[error("The role {0} was not found the app {1}")]
     RoleNotFound(RoleName, InstalledAppId),
+
+    #[error("The integrity zomes of app {0} have changed, so it needs to be migrated to a new app: {1:?}")]
+    IntegrityZomesChanged(InstalledAppId, Vec<IntegrityZomeChange>),
+
+    #[error(
+        "The user declined the content security policy relaxations of the new version of app {0}"
+    )]
+    ContentSecurityPolicyDeclined(InstalledAppId),
+}
+
+/// An integrity zome that is different in the new version of an app, which changes the hash of its DNA
+#[derive(Serialize, Debug, Clone)]
+pub struct IntegrityZomeChange {
+    pub role_name: RoleName,
+    pub zome_name: ZomeName,
+}
+
+/// Compares the integrity zomes of the new DNA files with the ones of the installed cells.
+/// Coordinator zomes can be updated in place, but any change in the integrity zomes needs a new cell
+pub async fn integrity_zome_changes(
+    admin_ws: &mut AdminWebsocket,
+    app: &AppInfo,
+    new_dna_files: &BTreeMap<RoleName, DnaFile>,
+) -> Result<Vec<IntegrityZomeChange>, UpdateAppError> {
+    let mut changes: Vec<IntegrityZomeChange> = Vec::new();
+
+    for (role_name, new_dna_file) in new_dna_files {
+        let cells = app
+            .cell_info
+            .get(role_name)
+            .ok_or(UpdateAppError::RoleNotFound(
+                role_name.clone(),
+                app.installed_app_id.clone(),
+            ))?;
+
+        // The clones of a cell share its integrity zomes
+        let Some(dna_hash) = cells.iter().find_map(|cell| match cell {
+            CellInfo::Provisioned(c) => Some(c.cell_id.dna_hash().clone()),
+            CellInfo::Stem(c) => Some(c.original_dna_hash.clone()),
+            CellInfo::Cloned(_) => None,
+        }) else {
+            continue;
+        };
+        let old_dna_def = admin_ws
+            .get_dna_definition(dna_hash)
+            .await
+            .map_err(|err| UpdateAppError::ConductorApiError(err))?;
+
+        for zome_name in changed_integrity_zomes(&old_dna_def, new_dna_file)? {
+            changes.push(IntegrityZomeChange {
+                role_name: role_name.clone(),
+                zome_name,
+            });
+        }
+    }
+
+    Ok(changes)
 }
 
 pub async fn update_app(
@@ -136,124 +723,365 @@ pub async fn update_app(
     );
 
     // Get the DNA def from the admin websocket
+    let app = get_app(admin_ws, &app_id).await?;
+
+    let new_dna_files = resolve_dna_files(bundle).await?;
+
+    // Updating only the coordinators of a DNA whose integrity zomes changed would leave the app broken
+    let integrity_changes = integrity_zome_changes(admin_ws, &app, &new_dna_files).await?;
+    if !integrity_changes.is_empty() {
+        return Err(UpdateAppError::IntegrityZomesChanged(
+            app_id,
+            integrity_changes,
+        ));
+    }
+
+    let mut updated = false;
+
+    for (role_name, dna_hash, new_dna_file) in app_cells(&app, new_dna_files)? {
+        let old_dna_def = admin_ws
+            .get_dna_definition(dna_hash.clone())
+            .await
+            .map_err(|err| UpdateAppError::ConductorApiError(err))?;
+
+        let mut zomes: Vec<ZomeManifest> = Vec::new();
+        let mut resources: Vec<(PathBuf, ResourceBytes)> = Vec::new();
+
+        for (zome_name, change) in coordinator_zome_changes(&old_dna_def, &new_dna_file)? {
+            match change {
+                CoordinatorZomeChange::Added => {
+                    log::info!("Adding new coordinator zome {zome_name} for role {role_name}")
+                }
+                CoordinatorZomeChange::Replaced => {
+                    log::info!("Updating coordinator zome {zome_name} for role {role_name}")
+                }
+            }
+            let (zome_manifest, resource) = coordinator_zome_resource(&new_dna_file, &zome_name)?;
+            zomes.push(zome_manifest);
+            resources.push(resource);
+        }
+
+        if !zomes.is_empty() {
+            let source: CoordinatorBundle =
+                Bundle::new(CoordinatorManifest { zomes }, resources, PathBuf::from("/"))?.into();
+            let req = UpdateCoordinatorsPayload {
+                dna_hash,
+                source: holochain_types::prelude::CoordinatorSource::Bundle(Box::new(source)),
+            };
+
+            admin_ws
+                .update_coordinators(req)
+                .await
+                .map_err(|err| UpdateAppError::ConductorApiError(err))?;
+            updated = true;
+        }
+    }
+
+    if updated {
+        if let AppInfoStatus::Running = app.status {
+            admin_ws
+                .disable_app(app_id.clone())
+                .await
+                .map_err(|err| UpdateAppError::ConductorApiError(err))?;
+            admin_ws
+                .enable_app(app_id.clone())
+                .await
+                .map_err(|err| UpdateAppError::ConductorApiError(err))?;
+        }
+        log::info!("Updated app {app_id:?}");
+    }
+
+    // The new coordinators may not implement the same resolvers anymore
+    forget_declared_hrl_resolvers(&app_id).await;
+
+    Ok(())
+}
+
+/// What updating a cell of the app to the new bundle would do
+#[derive(Serialize, Debug, Clone)]
+pub struct CellUpdatePlan {
+    pub role_name: RoleName,
+    pub dna_hash: DnaHash,
+    pub added_coordinator_zomes: Vec<ZomeName>,
+    pub replaced_coordinator_zomes: Vec<ZomeName>,
+    /// If there are any, the cell can't be updated in place and the app needs to be migrated
+    pub changed_integrity_zomes: Vec<ZomeName>,
+}
+
+/// What updating the app to the new bundle would do, without doing it
+#[derive(Serialize, Debug, Clone)]
+pub struct AppUpdatePlan {
+    pub cells: Vec<CellUpdatePlan>,
+    /// Always false for the apps without UI
+    pub ui_changed: bool,
+    /// Whether the app would be disabled and enabled again so that the new coordinator zomes get loaded
+    pub requires_restart: bool,
+}
+
+impl AppUpdatePlan {
+    /// Updating the app would fail with [`UpdateAppError::IntegrityZomesChanged`]
+    pub fn requires_migration(&self) -> bool {
+        self.cells
+            .iter()
+            .any(|cell| !cell.changed_integrity_zomes.is_empty())
+    }
+}
+
+/// Computes what [`update_app`] would do with the bundle, without updating any coordinators
+pub async fn plan_app_update(
+    admin_ws: &mut AdminWebsocket,
+    app_id: String,
+    bundle: AppBundle,
+) -> Result<AppUpdatePlan, UpdateAppError> {
+    let app = get_app(admin_ws, &app_id).await?;
+
+    let new_dna_files = resolve_dna_files(bundle).await?;
+
+    let mut cells: Vec<CellUpdatePlan> = Vec::new();
+
+    for (role_name, dna_hash, new_dna_file) in app_cells(&app, new_dna_files)? {
+        let old_dna_def = admin_ws
+            .get_dna_definition(dna_hash.clone())
+            .await
+            .map_err(|err| UpdateAppError::ConductorApiError(err))?;
+
+        let mut added_coordinator_zomes: Vec<ZomeName> = Vec::new();
+        let mut replaced_coordinator_zomes: Vec<ZomeName> = Vec::new();
+        for (zome_name, change) in coordinator_zome_changes(&old_dna_def, &new_dna_file)? {
+            match change {
+                CoordinatorZomeChange::Added => added_coordinator_zomes.push(zome_name),
+                CoordinatorZomeChange::Replaced => replaced_coordinator_zomes.push(zome_name),
+            }
+        }
+
+        cells.push(CellUpdatePlan {
+            role_name,
+            dna_hash,
+            added_coordinator_zomes,
+            replaced_coordinator_zomes,
+            changed_integrity_zomes: changed_integrity_zomes(&old_dna_def, &new_dna_file)?,
+        });
+    }
+
+    let coordinators_change = cells.iter().any(|cell| {
+        !cell.added_coordinator_zomes.is_empty() || !cell.replaced_coordinator_zomes.is_empty()
+    });
+    let requires_restart = coordinators_change && matches!(app.status, AppInfoStatus::Running);
+
+    Ok(AppUpdatePlan {
+        cells,
+        ui_changed: false,
+        requires_restart,
+    })
+}
+
+/// Computes what [`update_web_app`] would do with the bundle, comparing its UI with the one of the installed web-app package
+pub async fn plan_web_app_update(
+    admin_ws: &mut AdminWebsocket,
+    fs: &FileSystem,
+    app_id: String,
+    bundle: WebAppBundle,
+) -> Result<AppUpdatePlan, UpdateAppError> {
+    let mut plan = plan_app_update(admin_ws, app_id.clone(), bundle.happ_bundle().await?).await?;
+
+    plan.ui_changed = web_app_ui_changed(fs, &app_id, &bundle)
+        .await
+        .map_err(|err| UpdateAppError::ReadWebAppError(format!("{err:?}")))?;
+
+    Ok(plan)
+}
+
+/// Without the package of the installed web-app the UI is assumed to change
+async fn web_app_ui_changed(
+    fs: &FileSystem,
+    app_id: &InstalledAppId,
+    bundle: &WebAppBundle,
+) -> crate::Result<bool> {
+    let Some(web_app_hash) = fs
+        .installed_apps_store()
+        .get_artifacts(app_id)?
+        .and_then(|artifacts| artifacts.web_app_hash)
+    else {
+        return Ok(true);
+    };
+    let Some(installed_web_app) = fs.webapp_store().get_webapp(&web_app_hash.into())? else {
+        return Ok(true);
+    };
+
+    // Zipping the same files again changes the bytes of the zip, like its timestamps, so the files are compared
+    let installed_ui = installed_web_app.web_ui_zip_bytes().await?;
+    let new_ui = bundle.web_ui_zip_bytes().await?;
+
+    Ok(!ui_files(installed_ui.into_owned().into_inner())?
+        .eq(&ui_files(new_ui.into_owned().into_inner())?))
+}
+
+/// The extracted files of the UI zip, by path
+fn ui_files(ui_zip: Vec<u8>) -> Result<BTreeMap<String, Vec<u8>>, FileSystemError> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(ui_zip))?;
+
+    let mut files = BTreeMap::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        if file.is_dir() {
+            continue;
+        }
+        let mut content = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut content)?;
+        files.insert(file.name().to_string(), content);
+    }
+
+    Ok(files)
+}
+
+async fn get_app(
+    admin_ws: &mut AdminWebsocket,
+    app_id: &InstalledAppId,
+) -> Result<AppInfo, UpdateAppError> {
     let apps = admin_ws
         .list_apps(None)
         .await
         .map_err(|err| UpdateAppError::ConductorApiError(err))?;
 
-    let mut app = apps
-        .into_iter()
-        .find(|app| app.installed_app_id.eq(&app_id))
-        .ok_or(UpdateAppError::AppNotFound(app_id.clone()))?;
+    apps.into_iter()
+        .find(|app| app.installed_app_id.eq(app_id))
+        .ok_or(UpdateAppError::AppNotFound(app_id.clone()))
+}
 
-    let new_dna_files = resolve_dna_files(bundle).await?;
-
-    let mut updated = false;
+/// Pairs each cell of the app, clones included, with the new DNA file for its role
+fn app_cells(
+    app: &AppInfo,
+    new_dna_files: BTreeMap<RoleName, DnaFile>,
+) -> Result<Vec<(RoleName, DnaHash, DnaFile)>, UpdateAppError> {
+    let mut app_cells: Vec<(RoleName, DnaHash, DnaFile)> = Vec::new();
 
     for (role_name, new_dna_file) in new_dna_files {
         let cells = app
             .cell_info
-            .remove(&role_name)
+            .get(&role_name)
             .ok_or(UpdateAppError::RoleNotFound(
                 role_name.clone(),
                 app.installed_app_id.clone(),
             ))?;
 
         for cell in cells {
-            let mut zomes: Vec<ZomeManifest> = Vec::new();
-            let mut resources: Vec<(PathBuf, ResourceBytes)> = Vec::new();
-
             let dna_hash = match cell {
                 CellInfo::Provisioned(c) => c.cell_id.dna_hash().clone(),
                 CellInfo::Cloned(c) => c.cell_id.dna_hash().clone(),
                 CellInfo::Stem(c) => c.original_dna_hash.clone(),
             };
-            let old_dna_def = admin_ws
-                .get_dna_definition(dna_hash.clone())
-                .await
-                .map_err(|err| UpdateAppError::ConductorApiError(err))?;
+            app_cells.push((role_name.clone(), dna_hash, new_dna_file.clone()));
+        }
+    }
 
-            for (zome_name, coordinator_zome) in new_dna_file.dna_def().coordinator_zomes.iter() {
-                let deps = coordinator_zome
-                    .clone()
-                    .erase_type()
-                    .dependencies()
-                    .to_vec();
-                let dependencies = deps
-                    .into_iter()
-                    .map(|name| ZomeDependency { name })
-                    .collect();
-
-                if let Some(old_zome_def) = old_dna_def
-                    .coordinator_zomes
-                    .iter()
-                    .find(|(zome, _)| zome.eq(&zome_name))
-                {
-                    if !old_zome_def
-                        .1
-                        .wasm_hash(&zome_name)?
-                        .eq(&coordinator_zome.wasm_hash(&zome_name)?)
-                    {
-                        log::info!("Updating coordinator zome {zome_name} for role {role_name}");
-                        let resource_path = PathBuf::from(zome_name.0.to_string());
-                        zomes.push(ZomeManifest {
-                            name: zome_name.clone(),
-                            hash: None,
-                            dylib: None,
-                            location: ZomeLocation::Bundled(resource_path.clone()),
-                            dependencies: Some(dependencies),
-                        });
-                        let wasm = new_dna_file.get_wasm_for_zome(&zome_name)?;
-                        resources.push((resource_path, wasm.code().to_vec().into()));
-                    }
-                } else {
-                    log::info!("Adding new coordinator zome {zome_name} for role {role_name}");
-                    let resource_path = PathBuf::from(zome_name.0.to_string());
-                    zomes.push(ZomeManifest {
-                        name: zome_name.clone(),
-                        hash: None,
-                        dylib: None,
-                        location: ZomeLocation::Bundled(resource_path.clone()),
-                        dependencies: Some(dependencies),
-                    });
-                    let wasm = new_dna_file.get_wasm_for_zome(&zome_name)?;
-                    resources.push((resource_path, wasm.code().to_vec().into()));
-                }
-            }
+    Ok(app_cells)
+}
+
+enum CoordinatorZomeChange {
+    Added,
+    Replaced,
+}
 
-            if !zomes.is_empty() {
-                let source: CoordinatorBundle =
-                    Bundle::new(CoordinatorManifest { zomes }, resources, PathBuf::from("/"))?
-                        .into();
-                let req = UpdateCoordinatorsPayload {
-                    dna_hash,
-                    source: holochain_types::prelude::CoordinatorSource::Bundle(Box::new(source)),
-                };
-
-                admin_ws
-                    .update_coordinators(req)
-                    .await
-                    .map_err(|err| UpdateAppError::ConductorApiError(err))?;
-                updated = true;
+/// The coordinator zomes of the new DNA file that are not installed yet or whose wasm differs from the installed one
+fn coordinator_zome_changes(
+    old_dna_def: &DnaDef,
+    new_dna_file: &DnaFile,
+) -> Result<Vec<(ZomeName, CoordinatorZomeChange)>, UpdateAppError> {
+    let mut changes: Vec<(ZomeName, CoordinatorZomeChange)> = Vec::new();
+
+    for (zome_name, coordinator_zome) in new_dna_file.dna_def().coordinator_zomes.iter() {
+        if let Some(old_zome_def) = old_dna_def
+            .coordinator_zomes
+            .iter()
+            .find(|(zome, _)| zome.eq(&zome_name))
+        {
+            if !old_zome_def
+                .1
+                .wasm_hash(&zome_name)?
+                .eq(&coordinator_zome.wasm_hash(&zome_name)?)
+            {
+                changes.push((zome_name.clone(), CoordinatorZomeChange::Replaced));
             }
+        } else {
+            changes.push((zome_name.clone(), CoordinatorZomeChange::Added));
         }
     }
 
-    if updated {
-        if let AppInfoStatus::Running = app.status {
-            admin_ws
-                .disable_app(app_id.clone())
-                .await
-                .map_err(|err| UpdateAppError::ConductorApiError(err))?;
-            admin_ws
-                .enable_app(app_id.clone())
-                .await
-                .map_err(|err| UpdateAppError::ConductorApiError(err))?;
+    Ok(changes)
+}
+
+/// The integrity zomes that were added, removed or whose wasm differs from the installed one
+fn changed_integrity_zomes(
+    old_dna_def: &DnaDef,
+    new_dna_file: &DnaFile,
+) -> Result<Vec<ZomeName>, UpdateAppError> {
+    let old_zome_names = old_dna_def.integrity_zomes.iter().map(|(name, _)| name);
+    let new_zome_names = new_dna_file
+        .dna_def()
+        .integrity_zomes
+        .iter()
+        .map(|(name, _)| name);
+    let zome_names: BTreeSet<&ZomeName> = old_zome_names.chain(new_zome_names).collect();
+
+    let mut changed: Vec<ZomeName> = Vec::new();
+
+    for zome_name in zome_names {
+        let old_zome = old_dna_def
+            .integrity_zomes
+            .iter()
+            .find(|(name, _)| name.eq(zome_name));
+        let new_zome = new_dna_file
+            .dna_def()
+            .integrity_zomes
+            .iter()
+            .find(|(name, _)| name.eq(zome_name));
+
+        let is_changed = match (old_zome, new_zome) {
+            (Some((_, old_zome)), Some((_, new_zome))) => !old_zome
+                .wasm_hash(zome_name)?
+                .eq(&new_zome.wasm_hash(zome_name)?),
+            _ => true,
+        };
+        if is_changed {
+            changed.push(zome_name.clone());
         }
-        log::info!("Updated app {app_id:?}");
     }
 
-    Ok(())
+    Ok(changed)
+}
+
+/// The manifest and the wasm of the coordinator zome to bundle it for `update_coordinators`
+fn coordinator_zome_resource(
+    new_dna_file: &DnaFile,
+    zome_name: &ZomeName,
+) -> Result<(ZomeManifest, (PathBuf, ResourceBytes)), UpdateAppError> {
+    let dependencies = new_dna_file
+        .dna_def()
+        .coordinator_zomes
+        .iter()
+        .find(|(name, _)| name.eq(zome_name))
+        .map(|(_, coordinator_zome)| {
+            coordinator_zome
+                .clone()
+                .erase_type()
+                .dependencies()
+                .iter()
+                .map(|name| ZomeDependency { name: name.clone() })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let resource_path = PathBuf::from(zome_name.0.to_string());
+    let zome_manifest = ZomeManifest {
+        name: zome_name.clone(),
+        hash: None,
+        dylib: None,
+        location: ZomeLocation::Bundled(resource_path.clone()),
+        dependencies: Some(dependencies),
+    };
+    let wasm = new_dna_file.get_wasm_for_zome(zome_name)?;
+
+    Ok((zome_manifest, (resource_path, wasm.code().to_vec().into())))
 }
 
 async fn resolve_dna_files(
@@ -0,0 +1,32 @@
+use std::collections::HashMap;
+
+use crate::HolochainExt;
+use holochain::prelude::{MembraneProof, RoleName};
+use holochain_client::AppInfo;
+use tauri::{command, AppHandle, Runtime, Window};
+
+/// Only the launcher can invite other agents to an app, the app windows can't do it without the user knowing
+#[command]
+pub(crate) async fn create_invite<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    app_id: String,
+    membrane_proofs: Option<HashMap<RoleName, MembraneProof>>,
+) -> crate::Result<String> {
+    app.holochain()?.authorize_launcher_window(&window)?;
+
+    app.holochain()?
+        .create_invite(app_id, membrane_proofs.unwrap_or_default())
+}
+
+/// Only the launcher can accept invites, since accepting one installs an app
+#[command]
+pub(crate) async fn accept_invite<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    link: String,
+) -> crate::Result<AppInfo> {
+    app.holochain()?.authorize_launcher_window(&window)?;
+
+    app.holochain()?.accept_invite(link).await
+}
@@ -1,4 +1,4 @@
-use crate::HolochainExt;
+use crate::{AppCatalogEntry, HolochainExt};
 use holochain_client::AppInfo;
 use tauri::{command, AppHandle, Runtime};
 
@@ -13,3 +13,10 @@ pub(crate) async fn list_apps<R: Runtime>(app: AppHandle<R>) -> crate::Result<Ve
 
     Ok(apps)
 }
+
+#[command]
+pub(crate) async fn list_installed_apps<R: Runtime>(
+    app: AppHandle<R>,
+) -> crate::Result<Vec<AppCatalogEntry>> {
+    app.holochain()?.list_installed_apps().await
+}
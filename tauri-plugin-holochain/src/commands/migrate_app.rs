@@ -0,0 +1,29 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use crate::HolochainExt;
+use holochain::prelude::{MembraneProof, RoleName};
+use holochain_client::{AppInfo, InstalledAppId};
+use holochain_types::web_app::WebAppBundle;
+use tauri::{command, AppHandle, Runtime, Window};
+
+/// Migrates the app to the given version of its web-app, for when updating it fails because its integrity zomes changed.
+/// Only the launcher can do this, since it installs a new app
+#[command]
+pub(crate) async fn migrate_app<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    app_id: InstalledAppId,
+    new_app_id: InstalledAppId,
+    web_app_bundle_path: PathBuf,
+    membrane_proofs: HashMap<RoleName, MembraneProof>,
+) -> crate::Result<AppInfo> {
+    let holochain = app.holochain()?;
+    holochain.authorize_launcher_window(&window)?;
+
+    let bytes = std::fs::read(web_app_bundle_path)?;
+    let web_app_bundle = WebAppBundle::decode(bytes.as_slice())?;
+
+    holochain
+        .migrate_web_app(app_id, new_app_id, web_app_bundle, membrane_proofs)
+        .await
+}
@@ -1,7 +1,28 @@
 use crate::HolochainExt;
-use tauri::{command, AppHandle, Runtime};
+use tauri::{command, AppHandle, Runtime, Window};
 
+/// Focuses the app's open window, or opens a new one if there is none or if `new_window` is set.
+/// `route` is of the form `/path?query` and gets forwarded to the app's UI
 #[command]
-pub(crate) async fn open_app<R: Runtime>(app: AppHandle<R>, app_id: String) -> crate::Result<()> {
-    app.holochain()?.open_app(app_id).await
+pub(crate) async fn open_app<R: Runtime>(
+    app: AppHandle<R>,
+    app_id: String,
+    route: Option<String>,
+    new_window: Option<bool>,
+) -> crate::Result<()> {
+    app.holochain()?
+        .open_app_window(app_id, route, new_window.unwrap_or(false))
+        .await?;
+    Ok(())
+}
+
+/// Called by the app window whenever the app's UI reports that it navigated,
+/// since the window can't read the route of the iframe of another origin
+#[command]
+pub(crate) fn set_window_route<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    route: String,
+) -> crate::Result<()> {
+    app.holochain()?.set_window_route(&window, route)
 }
@@ -0,0 +1,40 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use crate::HolochainExt;
+use holochain::prelude::{MembraneProof, NetworkSeed, RoleName};
+use holochain_client::AppInfo;
+use holochain_types::web_app::WebAppBundle;
+use tauri::{command, AppHandle, Runtime, Window};
+
+/// Only the launcher can install apps, app windows can't install other apps
+#[command]
+pub(crate) async fn install_web_app_with_deferred_membrane_proofs<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    app_id: String,
+    web_app_bundle_path: PathBuf,
+    network_seed: Option<NetworkSeed>,
+) -> crate::Result<()> {
+    let holochain = app.holochain()?;
+    holochain.authorize_launcher_window(&window)?;
+
+    let bytes = std::fs::read(web_app_bundle_path)?;
+    let web_app_bundle = WebAppBundle::decode(bytes.as_slice())?;
+
+    holochain
+        .install_web_app_with_deferred_membrane_proofs(app_id, web_app_bundle, network_seed)
+        .await
+}
+
+#[command]
+pub(crate) async fn provide_membrane_proofs<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    membrane_proofs: HashMap<RoleName, MembraneProof>,
+) -> crate::Result<AppInfo> {
+    let app_id = app.holochain()?.window_app_id(&window)?;
+
+    app.holochain()?
+        .provide_membrane_proofs(app_id, membrane_proofs)
+        .await
+}
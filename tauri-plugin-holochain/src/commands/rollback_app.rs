@@ -0,0 +1,14 @@
+use crate::HolochainExt;
+use holochain_client::InstalledAppId;
+use tauri::{command, AppHandle, Runtime, Window};
+
+#[command]
+pub(crate) async fn rollback_app<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    app_id: InstalledAppId,
+) -> crate::Result<()> {
+    app.holochain()?.authorize_launcher_window(&window)?;
+
+    app.holochain()?.rollback_app(app_id).await
+}
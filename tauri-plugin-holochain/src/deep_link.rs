@@ -0,0 +1,135 @@
+use std::sync::Mutex;
+
+use holochain::prelude::{AnyDhtHash, AnyDhtHashB64, DnaHash, DnaHashB64};
+use hrl::Hrl;
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::HolochainExt;
+
+pub const HRL_SCHEME: &str = "hrl";
+
+/// Deep links received before holochain was ready, which are opened as soon as it is
+#[derive(Default)]
+pub(crate) struct PendingDeepLinks(pub Mutex<Vec<Hrl>>);
+
+/// Parses a deep link of the form `hrl://<DNA_HASH>/<RESOURCE_HASH>`
+pub fn parse_hrl(url: &str) -> crate::Result<Hrl> {
+    let malformed = || crate::Error::MalformedDeepLink(url.to_string());
+
+    let hrl_path = url
+        .trim()
+        .strip_prefix(format!("{HRL_SCHEME}://").as_str())
+        .ok_or(malformed())?;
+
+    let components: Vec<&str> = hrl_path.split('/').filter(|c| !c.is_empty()).collect();
+
+    let [dna_hash, resource_hash] = components.as_slice() else {
+        return Err(malformed());
+    };
+
+    let dna_hash = DnaHashB64::from_b64_str(dna_hash).map_err(|_| malformed())?;
+    let resource_hash = AnyDhtHashB64::from_b64_str(resource_hash).map_err(|_| malformed())?;
+
+    Ok(Hrl {
+        dna_hash: DnaHash::from(dna_hash),
+        resource_hash: AnyDhtHash::from(resource_hash),
+    })
+}
+
+pub fn hrl_to_url(hrl: &Hrl) -> String {
+    format!(
+        "{HRL_SCHEME}://{}/{}",
+        DnaHashB64::from(hrl.dna_hash.clone()),
+        AnyDhtHashB64::from(hrl.resource_hash.clone())
+    )
+}
+
+/// Opens the app that owns the HRL of the deep link,
+/// or queues it to be opened when holochain is ready if this is a cold start
+pub fn handle_deep_link<R: Runtime>(app_handle: &AppHandle<R>, url: String) -> crate::Result<()> {
+    log::info!("Received deep link {url}");
+
+    let hrl = parse_hrl(url.as_str())?;
+
+    let pending_deep_links = app_handle.state::<PendingDeepLinks>();
+    let mut pending_deep_links = pending_deep_links
+        .0
+        .lock()
+        .expect("Could not lock the pending deep links");
+
+    if app_handle.holochain().is_err() {
+        pending_deep_links.push(hrl);
+        return Ok(());
+    }
+
+    open_hrl_in_background(app_handle.clone(), hrl);
+
+    Ok(())
+}
+
+/// Opens the deep links that were received before holochain was ready
+pub(crate) fn open_pending_deep_links<R: Runtime>(app_handle: &AppHandle<R>) {
+    let pending_deep_links = app_handle.state::<PendingDeepLinks>();
+    let hrls: Vec<Hrl> = pending_deep_links
+        .0
+        .lock()
+        .expect("Could not lock the pending deep links")
+        .drain(..)
+        .collect();
+
+    for hrl in hrls {
+        open_hrl_in_background(app_handle.clone(), hrl);
+    }
+}
+
+fn open_hrl_in_background<R: Runtime>(app_handle: AppHandle<R>, hrl: Hrl) {
+    tauri::async_runtime::spawn(async move {
+        let result = match app_handle.holochain() {
+            Ok(holochain) => holochain.open_hrl(hrl).await,
+            Err(err) => Err(err),
+        };
+        if let Err(err) = result {
+            log::error!("Failed to open deep link: {err:?}");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use holochain::prelude::ActionHash;
+
+    use super::*;
+
+    fn hrl() -> Hrl {
+        Hrl {
+            dna_hash: DnaHash::from_raw_32(vec![1; 32]),
+            resource_hash: ActionHash::from_raw_32(vec![2; 32]).into(),
+        }
+    }
+
+    #[test]
+    fn parse_hrl_roundtrip() {
+        let hrl = hrl();
+        let parsed = parse_hrl(hrl_to_url(&hrl).as_str()).unwrap();
+
+        assert_eq!(parsed.dna_hash, hrl.dna_hash);
+        assert_eq!(parsed.resource_hash, hrl.resource_hash);
+    }
+
+    #[test]
+    fn parse_hrl_ignores_surrounding_whitespace_and_slashes() {
+        let url = format!(" {}/ ", hrl_to_url(&hrl()));
+
+        assert!(parse_hrl(url.as_str()).is_ok());
+    }
+
+    #[test]
+    fn parse_hrl_rejects_malformed_links() {
+        let dna_hash = DnaHashB64::from(hrl().dna_hash).to_string();
+
+        assert!(parse_hrl("https://example.com").is_err());
+        assert!(parse_hrl(format!("{HRL_SCHEME}://{dna_hash}").as_str()).is_err());
+        assert!(parse_hrl(format!("{HRL_SCHEME}://{dna_hash}/notahash").as_str()).is_err());
+        assert!(parse_hrl(format!("{}/extra", hrl_to_url(&hrl())).as_str()).is_err());
+    }
+}
@@ -0,0 +1,353 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use holochain::prelude::AppBundle;
+use holochain_client::InstalledAppId;
+use holochain_types::web_app::WebAppBundle;
+use hyper::{
+    header::{HeaderName, CONNECTION, CONTENT_SECURITY_POLICY, HOST, UPGRADE},
+    http::{HeaderMap, HeaderValue},
+    upgrade, Body, Request, Response, StatusCode, Uri,
+};
+use tauri::{async_runtime::JoinHandle, AppHandle, Runtime};
+
+use crate::{
+    asset_resolvers::{
+        https_client, register_asset_resolver, unregister_asset_resolver, ProxyAssetResolver,
+    },
+    filesystem::UiMetadata,
+    HolochainExt, UpdateAppError,
+};
+
+/// How often the watched bundles are checked for changes
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The dev servers that the UIs of the apps in development are proxied to, by app id
+static DEV_SERVERS: RwLock<BTreeMap<InstalledAppId, url::Url>> = RwLock::new(BTreeMap::new());
+
+/// Serves the UI of the installed app from the given dev server instead of from its stored UI,
+/// so that changes to the UI show up without rebuilding and reinstalling the web-app
+pub async fn enable_dev_mode(app_id: InstalledAppId, dev_server_url: url::Url) {
+    log::info!("Serving the UI of app {app_id} from the dev server at {dev_server_url}");
+
+    let resolver = ProxyAssetResolver::new(dev_server_url.clone())
+        .with_ui_metadata(dev_ui_metadata(&dev_server_url));
+    register_asset_resolver(app_id.clone(), Arc::new(resolver)).await;
+
+    DEV_SERVERS
+        .write()
+        .expect("Could not write the dev servers")
+        .insert(app_id, dev_server_url);
+}
+
+/// Goes back to serving the stored UI of the app
+pub async fn disable_dev_mode(app_id: &InstalledAppId) {
+    unregister_asset_resolver(app_id).await;
+
+    DEV_SERVERS
+        .write()
+        .expect("Could not write the dev servers")
+        .remove(app_id);
+}
+
+/// Returns the dev server of the app if its UI is in development.
+/// Hosts are lowercased by the webviews, so app ids are compared ignoring their case
+pub fn dev_server_url(app_id: &InstalledAppId) -> Option<url::Url> {
+    DEV_SERVERS
+        .read()
+        .expect("Could not read the dev servers")
+        .iter()
+        .find(|(dev_app_id, _)| dev_app_id.eq_ignore_ascii_case(app_id))
+        .map(|(_, url)| url.clone())
+}
+
+/// The tasks watching the bundles of the apps in development, by app id
+static BUNDLE_WATCHERS: Mutex<BTreeMap<InstalledAppId, JoinHandle<()>>> =
+    Mutex::new(BTreeMap::new());
+
+/// Splits a dev argument of the form `<app_id>=<value>`
+fn split_dev_arg<'a>(arg: &'a str, value_name: &str) -> crate::Result<(InstalledAppId, &'a str)> {
+    match arg.split_once('=') {
+        Some((app_id, value)) => Ok((app_id.trim().to_string(), value.trim())),
+        None => Err(crate::Error::DevModeError(format!(
+            "{arg} is not of the form <app_id>=<{value_name}>"
+        ))),
+    }
+}
+
+/// Parses a dev server argument of the form `<app_id>=<url>`
+pub fn parse_dev_server_arg(arg: &str) -> crate::Result<(InstalledAppId, url::Url)> {
+    let (app_id, url) = split_dev_arg(arg, "url")?;
+
+    let url = url::Url::parse(url)?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(crate::Error::DevModeError(format!(
+            "{url} is not an http url"
+        )));
+    }
+
+    Ok((app_id, url))
+}
+
+/// Parses a dev bundle argument of the form `<app_id>=<path to the .happ or .webhapp>`
+pub fn parse_dev_bundle_arg(arg: &str) -> crate::Result<(InstalledAppId, PathBuf)> {
+    let (app_id, path) = split_dev_arg(arg, "path")?;
+
+    Ok((app_id, PathBuf::from(path)))
+}
+
+/// Watches the `.happ` or `.webhapp` file at the given path, updating the installed app and reloading its windows
+/// every time the file changes. Replaces the previous watcher for the app, if any
+pub fn watch_app_bundle<R: Runtime>(
+    app_handle: AppHandle<R>,
+    app_id: InstalledAppId,
+    bundle_path: PathBuf,
+) {
+    log::info!("Watching {bundle_path:?} to update app {app_id}");
+
+    let watched_app_id = app_id.clone();
+    let watcher = tauri::async_runtime::spawn(async move {
+        let mut last_modification = bundle_modification(&bundle_path);
+        let mut changed = false;
+
+        loop {
+            async_std::task::sleep(WATCH_INTERVAL).await;
+
+            let modification = bundle_modification(&bundle_path);
+            if modification.is_none() {
+                // The bundle is being rebuilt
+                continue;
+            }
+            if modification.ne(&last_modification) {
+                // Wait until the bundle has been completely written before reading it
+                last_modification = modification;
+                changed = true;
+                continue;
+            }
+            if !changed {
+                continue;
+            }
+            changed = false;
+
+            if let Err(err) = reload_app_bundle(&app_handle, &watched_app_id, &bundle_path).await {
+                log::error!("Failed to update app {watched_app_id} from {bundle_path:?}: {err:?}");
+            }
+        }
+    });
+
+    if let Some(previous_watcher) = BUNDLE_WATCHERS
+        .lock()
+        .expect("Could not lock the bundle watchers")
+        .insert(app_id, watcher)
+    {
+        previous_watcher.abort();
+    }
+}
+
+/// Stops watching the bundle of the app
+pub fn unwatch_app_bundle(app_id: &InstalledAppId) {
+    if let Some(watcher) = BUNDLE_WATCHERS
+        .lock()
+        .expect("Could not lock the bundle watchers")
+        .remove(app_id)
+    {
+        watcher.abort();
+    }
+}
+
+fn bundle_modification(bundle_path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(bundle_path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Updates the app with the bundle, which only applies the changes in its coordinator zomes
+async fn reload_app_bundle<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    app_id: &InstalledAppId,
+    bundle_path: &Path,
+) -> crate::Result<()> {
+    log::info!("Bundle {bundle_path:?} changed, updating app {app_id}");
+
+    let bytes = std::fs::read(bundle_path)?;
+    let holochain = app_handle.holochain()?;
+
+    let result = match bundle_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+    {
+        Some("webhapp") => {
+            let web_app_bundle = WebAppBundle::decode(bytes.as_slice())?;
+            holochain
+                .update_web_app(app_id.clone(), web_app_bundle)
+                .await
+        }
+        _ => {
+            let app_bundle: AppBundle = mr_bundle::Bundle::decode(bytes.as_slice())?.into();
+            holochain.update_app(app_id.clone(), app_bundle).await
+        }
+    };
+    if let Err(UpdateAppError::IntegrityZomesChanged(_, changes)) = &result {
+        for change in changes {
+            log::warn!(
+                "!!! Integrity zome {} for role {} of app {app_id} has changed: integrity changes can't be applied \
+                to the installed app, reinstall it or migrate it to apply them !!!",
+                change.zome_name,
+                change.role_name
+            );
+        }
+    }
+    result.map_err(|err| crate::Error::DevModeError(format!("{err:?}")))?;
+
+    holochain.reload_app_windows(app_id)?;
+    log::info!("Reloaded app {app_id} from {bundle_path:?}");
+
+    Ok(())
+}
+
+/// Dev servers load their client from their own origin and keep a websocket open for hot module replacement,
+/// and the UI frameworks inject inline scripts while in development
+fn dev_ui_metadata(dev_server_url: &url::Url) -> UiMetadata {
+    let origin = dev_server_url.origin().ascii_serialization();
+    let websocket_origin = origin.replacen("http", "ws", 1);
+
+    let mut content_security_policy = BTreeMap::new();
+    content_security_policy.insert(String::from("default-src"), vec![origin.clone()]);
+    content_security_policy.insert(
+        String::from("script-src"),
+        vec![origin.clone(), String::from("'unsafe-inline'")],
+    );
+    content_security_policy.insert(
+        String::from("connect-src"),
+        vec![
+            origin,
+            websocket_origin,
+            // The websocket through the http server, which WebKit doesn't consider covered by 'self'
+            String::from("ws://*.localhost:*"),
+        ],
+    );
+
+    UiMetadata {
+        content_security_policy,
+        ..Default::default()
+    }
+}
+
+/// Forwards the request to the dev server, query included, since dev servers
+/// transform their modules depending on it, but without the hop-by-hop headers. Websocket upgrades are forwarded as well,
+/// piping the upgraded connections into each other
+pub async fn proxy_to_dev_server(
+    mut request: Request<Body>,
+    dev_server_url: &url::Url,
+    content_security_policy: &String,
+) -> crate::Result<Response<Body>> {
+    let path_and_query = request
+        .uri()
+        .path_and_query()
+        .map(|p| p.as_str().trim_start_matches('/').to_string())
+        .unwrap_or_default();
+    let url = dev_server_url.join(path_and_query.as_str())?;
+    let uri: Uri = url.as_str().parse().map_err(|err| {
+        crate::Error::HttpServerError(format!("Invalid dev server url {url}: {err:?}"))
+    })?;
+
+    let is_upgrade = request.headers().contains_key(UPGRADE);
+
+    let mut proxied_request = Request::builder().method(request.method()).uri(uri);
+    for (name, value) in request.headers() {
+        // The client sets the host of the dev server, which may reject requests for other hosts
+        if name.ne(&HOST) && !is_hop_by_hop_header(request.headers(), name, is_upgrade) {
+            proxied_request = proxied_request.header(name, value);
+        }
+    }
+    let body = match is_upgrade {
+        true => Body::empty(),
+        false => std::mem::replace(request.body_mut(), Body::empty()),
+    };
+    let proxied_request = proxied_request
+        .body(body)
+        .map_err(|err| crate::Error::HttpServerError(format!("{err:?}")))?;
+
+    let mut proxied_response = https_client()
+        .request(proxied_request)
+        .await
+        .map_err(|err| {
+            crate::Error::HttpServerError(format!(
+                "Failed to proxy {url} to the dev server: {err:?}"
+            ))
+        })?;
+
+    if proxied_response
+        .status()
+        .ne(&StatusCode::SWITCHING_PROTOCOLS)
+    {
+        let hop_by_hop_headers: Vec<HeaderName> = proxied_response
+            .headers()
+            .keys()
+            .filter(|name| is_hop_by_hop_header(proxied_response.headers(), name, false))
+            .cloned()
+            .collect();
+        for name in hop_by_hop_headers {
+            proxied_response.headers_mut().remove(name);
+        }
+
+        let content_security_policy = HeaderValue::from_str(content_security_policy.as_str())
+            .map_err(|err| crate::Error::HttpServerError(format!("{err:?}")))?;
+        proxied_response
+            .headers_mut()
+            .insert(CONTENT_SECURITY_POLICY, content_security_policy);
+        return Ok(proxied_response);
+    }
+
+    let mut response = Response::builder().status(StatusCode::SWITCHING_PROTOCOLS);
+    for (name, value) in proxied_response.headers() {
+        if !is_hop_by_hop_header(proxied_response.headers(), name, true) {
+            response = response.header(name, value);
+        }
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let upgraded = futures::future::try_join(
+            upgrade::on(&mut request),
+            upgrade::on(&mut proxied_response),
+        )
+        .await;
+        match upgraded {
+            Ok((mut client, mut dev_server)) => {
+                if let Err(err) = tokio::io::copy_bidirectional(&mut client, &mut dev_server).await
+                {
+                    log::warn!("Websocket to the dev server closed: {err:?}");
+                }
+            }
+            Err(err) => log::error!("Failed to upgrade the connection to the dev server: {err:?}"),
+        }
+    });
+
+    response
+        .body(Body::empty())
+        .map_err(|err| crate::Error::HttpServerError(format!("{err:?}")))
+}
+
+/// Whether the header only concerns the connection it arrived on, so it must not be forwarded:
+/// the standard hop-by-hop headers and the ones listed in the `Connection` header.
+/// For websockets the `Connection` and `Upgrade` headers are kept, since they switch the protocol
+fn is_hop_by_hop_header(headers: &HeaderMap, name: &HeaderName, is_upgrade: bool) -> bool {
+    if name.eq(&CONNECTION) || name.eq(&UPGRADE) {
+        return !is_upgrade;
+    }
+
+    let listed_in_connection = headers
+        .get_all(CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|listed| listed.trim().eq_ignore_ascii_case(name.as_str()));
+
+    match name.as_str() {
+        "keep-alive" | "transfer-encoding" | "te" | "trailer" | "trailers" => true,
+        name => name.starts_with("proxy-") || listed_in_connection,
+    }
+}
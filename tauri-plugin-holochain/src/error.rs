@@ -46,6 +46,9 @@ pub enum Error {
     #[error(transparent)]
     UrlParseError(#[from] url::ParseError),
 
+    #[error(transparent)]
+    SerdeJsonError(#[from] serde_json::Error),
+
     #[error("ConductorApiError: `{0:?}`")]
     ConductorApiError(ConductorApiError),
 
@@ -67,6 +70,48 @@ pub enum Error {
     #[error("Error opening app: {0}")]
     OpenAppError(String),
 
+    #[error("Invalid route for an app window: {0}")]
+    InvalidRoute(String),
+
+    #[error("The window {0} does not belong to any app")]
+    WindowNotFromAnApp(String),
+
+    #[error("There is no pending installation for app {0}")]
+    NoPendingInstallation(InstalledAppId),
+
+    #[error("The window {0} is not authorized to act on app {1}")]
+    UnauthorizedWindow(String, InstalledAppId),
+
+    #[error("Only the launcher windows are authorized for this, and {0} is not one of them")]
+    NotALauncherWindow(String),
+
+    #[error("The user declined the content security policy relaxations of app {0}")]
+    ContentSecurityPolicyDeclined(InstalledAppId),
+
+    #[error("Malformed invite: {0}")]
+    MalformedInvite(String),
+
+    #[error("Invite error: {0}")]
+    InviteError(String),
+
+    #[error("Malformed deep link: {0}")]
+    MalformedDeepLink(String),
+
+    #[error("Could not find a resolver for the HRL: {0}")]
+    HrlResolverNotFound(String),
+
+    #[error("Malformed asset request: {0}")]
+    MalformedAssetRequest(String),
+
+    #[error("Migration error: {0}")]
+    MigrationError(String),
+
+    #[error("Rollback error: {0}")]
+    RollbackError(String),
+
+    #[error("Dev mode error: {0}")]
+    DevModeError(String),
+
     #[error("Holochain has not been initialized yet")]
     HolochainNotInitialized,
 }
@@ -1,18 +1,26 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
 use std::{fs, io::Write};
 
 use holochain::prelude::*;
 use holochain_types::web_app::WebAppBundle;
 use mr_bundle::error::MrBundleError;
+use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Manager, Runtime};
 use zip::result::ZipError;
 
+use crate::assets::ContentEncoding;
+use crate::catalog::AppSource;
 use crate::launch::{get_config, vec_to_locked};
+use crate::ui_archive::{archive_index, forget_archive_index};
 
 #[derive(Clone)]
 pub struct FileSystem {
     pub app_data_dir: PathBuf,
     pub app_config_dir: PathBuf,
+    pub ui_storage: UiStorage,
 }
 /// Returns a string considering the relevant part of the version regarding breaking changes
 /// Examples:
@@ -43,11 +51,14 @@ impl FileSystem {
         let fs = FileSystem {
             app_data_dir,
             app_config_dir,
+            ui_storage: UiStorage::default(),
         };
 
         fs::create_dir_all(fs.webapp_store().path)?;
         fs::create_dir_all(fs.icon_store().path)?;
         fs::create_dir_all(fs.ui_store().path)?;
+        fs::create_dir_all(fs.installed_apps_store().path)?;
+        fs::create_dir_all(fs.window_state_store().path)?;
         fs::create_dir_all(fs.keystore_dir())?;
 
         //#[cfg(target_family = "unix")]
@@ -87,6 +98,25 @@ impl FileSystem {
     pub fn ui_store(&self) -> UiStore {
         UiStore {
             path: self.app_data_dir.join("uis"),
+            storage: self.ui_storage,
+        }
+    }
+
+    pub fn installed_apps_store(&self) -> InstalledAppsStore {
+        InstalledAppsStore {
+            path: self.app_data_dir.join("installed-apps"),
+        }
+    }
+
+    pub fn window_state_store(&self) -> WindowStateStore {
+        WindowStateStore {
+            path: self.app_config_dir.join("windows"),
+        }
+    }
+
+    pub fn locale_store(&self) -> LocaleStore {
+        LocaleStore {
+            path: self.app_config_dir.join("locale.json"),
         }
     }
 }
@@ -103,15 +133,136 @@ pub enum FileSystemError {
     ZipError(#[from] ZipError),
 }
 
+/// Name of the optional file at the root of the UI zip in which apps declare how their UI is served
+pub const UI_METADATA_FILE: &'static str = "ui-metadata.json";
+
+/// How the app's UI needs to be served, as declared in its [`UI_METADATA_FILE`]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct UiMetadata {
+    /// Serve `index.html` for the navigation requests that don't match any file, for client-side routed apps
+    #[serde(default)]
+    pub spa_fallback: bool,
+    /// Sources added to the directives of the default content security policy,
+    /// like `"connect-src": ["https://example.com"]`, which are shown to the user before installing the app
+    #[serde(default)]
+    pub content_security_policy: BTreeMap<String, Vec<String>>,
+    /// The locales that the UI is translated to, like `["en", "sv-SE"]`
+    #[serde(default)]
+    pub locales: Vec<String>,
+    /// The title of the app's windows by locale, like `{ "en": "Gather", "sv": "Samla" }`
+    #[serde(default)]
+    pub title: BTreeMap<String, String>,
+    /// The description of the app shown in the launcher by locale, like the title
+    #[serde(default)]
+    pub description: BTreeMap<String, String>,
+    /// The version of the app shown in the launcher, like `"1.2.0"`
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Path of the app's icon inside the UI zip, like `"icon.png"`
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// The zome functions through which the previous version of the app hands its data over to this one,
+    /// by role, for when this version changes the integrity zomes and the app needs to be migrated
+    #[serde(default)]
+    pub migration: BTreeMap<RoleName, MigrationFunctions>,
+    /// The UI fetches or constructs the membrane proofs itself, so the app needs to be installed
+    /// with `install_web_app_with_deferred_membrane_proofs`
+    #[serde(default)]
+    pub deferred_membrane_proofs: bool,
+    /// The coordinator zome that answers the HRL resolver functions for the HRLs of each role's DNA,
+    /// like `{ "gather": "events" }`
+    #[serde(default)]
+    pub hrl_resolvers: BTreeMap<RoleName, ZomeName>,
+}
+
+/// The export function is called in the cell of the previous version of the app,
+/// and its result is passed as is to the import function in the cell of the new version
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MigrationFunctions {
+    pub zome_name: ZomeName,
+    pub export_fn_name: FunctionName,
+    pub import_fn_name: FunctionName,
+}
+
+/// Reads the [`UiMetadata`] from the UI of the web-app without installing it
+pub async fn read_web_app_ui_metadata(web_app: &WebAppBundle) -> crate::Result<UiMetadata> {
+    let ui_bytes = web_app.web_ui_zip_bytes().await?;
+    let mut archive =
+        zip::ZipArchive::new(std::io::Cursor::new(ui_bytes.into_owned().into_inner()))
+            .map_err(|err| FileSystemError::ZipError(err))?;
+
+    let metadata = match archive.by_name(UI_METADATA_FILE) {
+        Ok(file) => serde_json::from_reader(file)?,
+        Err(ZipError::FileNotFound) => UiMetadata::default(),
+        Err(err) => Err(FileSystemError::ZipError(err))?,
+    };
+
+    Ok(metadata)
+}
+
+/// How the UIs of the installed apps are stored
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum UiStorage {
+    /// Every file of the UI is extracted into a folder, next to its precompressed variants
+    #[default]
+    Extracted,
+    /// The UI zip is kept as is, and its files are read from the archive on demand
+    Archive,
+}
+
 pub struct UiStore {
     path: PathBuf,
+    storage: UiStorage,
 }
 
+/// The metadata of the stored UIs that has already been read, by path of the UI,
+/// since it's needed to serve every asset
+static UI_METADATA: RwLock<BTreeMap<PathBuf, UiMetadata>> = RwLock::new(BTreeMap::new());
+
+/// Bumped whenever a stored UI is replaced or removed, so that what was read from it
+/// while that was happening doesn't get cached
+pub(crate) static UI_GENERATION: AtomicU64 = AtomicU64::new(0);
+
 impl UiStore {
     pub fn ui_path(&self, installed_app_id: &InstalledAppId) -> PathBuf {
         self.path.join(installed_app_id)
     }
 
+    pub fn ui_archive_path(&self, installed_app_id: &InstalledAppId) -> PathBuf {
+        self.path.join(format!("{installed_app_id}.zip"))
+    }
+
+    /// Whether the app has a UI stored, in either of the storages
+    pub fn has_ui(&self, installed_app_id: &InstalledAppId) -> bool {
+        self.ui_archive_path(installed_app_id).exists() || self.ui_path(installed_app_id).exists()
+    }
+
+    /// The ids of the apps that have a UI stored, in either of the storages
+    pub fn list_uis(&self) -> Result<BTreeSet<InstalledAppId>, FileSystemError> {
+        let mut installed_app_ids = BTreeSet::new();
+
+        for entry in fs::read_dir(&self.path)? {
+            let path = entry?.path();
+            let is_leftover = path
+                .extension()
+                .map(|e| e.eq("tmp") || e.eq("old"))
+                .unwrap_or(false);
+            let installed_app_id = match path.is_dir() {
+                // Folders left behind by an interrupted replacement of a UI
+                true if is_leftover => None,
+                true => path.file_name(),
+                false if path.extension().map(|e| e.eq("zip")).unwrap_or(false) => path.file_stem(),
+                false => None,
+            };
+            if let Some(installed_app_id) = installed_app_id.and_then(|s| s.to_str()) {
+                installed_app_ids.insert(installed_app_id.to_string());
+            }
+        }
+
+        Ok(installed_app_ids)
+    }
+
     pub async fn extract_and_store_ui(
         &self,
         installed_app_id: &InstalledAppId,
@@ -119,22 +270,185 @@ impl UiStore {
     ) -> Result<(), FileSystemError> {
         let ui_bytes = web_app.web_ui_zip_bytes().await?;
 
+        // The previous UI is only replaced once the new one has been stored, so a failed update keeps it
+        let result = self.store_ui(installed_app_id, ui_bytes.into_owned().into_inner());
+
+        // The assets requested while the UI was being stored may have cached what was there before
+        self.forget_cached_ui(installed_app_id);
+
+        result
+    }
+
+    fn store_ui(
+        &self,
+        installed_app_id: &InstalledAppId,
+        ui_bytes: Vec<u8>,
+    ) -> Result<(), FileSystemError> {
         let ui_folder_path = self.ui_path(installed_app_id);
+        let ui_archive_path = self.ui_archive_path(installed_app_id);
+        let ui_zip_path = self.path.join(format!("{installed_app_id}.zip.tmp"));
 
-        if ui_folder_path.exists() {
-            fs::remove_dir_all(&ui_folder_path)?;
+        match self.storage {
+            UiStorage::Archive => {
+                fs::write(ui_zip_path.clone(), &ui_bytes)?;
+
+                // Check that the archive is readable before replacing the previous UI with it
+                if let Err(err) = zip::ZipArchive::new(fs::File::open(ui_zip_path.clone())?) {
+                    fs::remove_file(&ui_zip_path)?;
+                    return Err(err.into());
+                }
+                fs::rename(ui_zip_path, ui_archive_path)?;
+
+                // Switching storages must not leave the UI from the other one behind
+                if ui_folder_path.exists() {
+                    fs::remove_dir_all(&ui_folder_path)?;
+                }
+            }
+            UiStorage::Extracted => {
+                let new_ui_folder_path = self.path.join(format!("{installed_app_id}.tmp"));
+                let old_ui_folder_path = self.path.join(format!("{installed_app_id}.old"));
+
+                if new_ui_folder_path.exists() {
+                    fs::remove_dir_all(&new_ui_folder_path)?;
+                }
+
+                let extracted = extract_ui(&ui_bytes, &ui_zip_path, &new_ui_folder_path);
+                if ui_zip_path.exists() {
+                    fs::remove_file(&ui_zip_path)?;
+                }
+                if let Err(err) = extracted {
+                    if new_ui_folder_path.exists() {
+                        fs::remove_dir_all(&new_ui_folder_path)?;
+                    }
+                    return Err(err);
+                }
+
+                // Swap the folders, so that the UI is never missing while it's being replaced
+                if old_ui_folder_path.exists() {
+                    fs::remove_dir_all(&old_ui_folder_path)?;
+                }
+                if ui_folder_path.exists() {
+                    fs::rename(&ui_folder_path, &old_ui_folder_path)?;
+                }
+                fs::rename(&new_ui_folder_path, &ui_folder_path)?;
+                if old_ui_folder_path.exists() {
+                    fs::remove_dir_all(&old_ui_folder_path)?;
+                }
+
+                // Switching storages must not leave the UI from the other one behind
+                if ui_archive_path.exists() {
+                    fs::remove_file(&ui_archive_path)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether the UI of the app has a file with the given path
+    pub fn ui_file_exists(
+        &self,
+        installed_app_id: &InstalledAppId,
+        name: &str,
+    ) -> Result<bool, FileSystemError> {
+        let ui_archive_path = self.ui_archive_path(installed_app_id);
+
+        if ui_archive_path.exists() {
+            return Ok(archive_index(&ui_archive_path)?.contains(name));
         }
 
-        fs::create_dir_all(&ui_folder_path)?;
+        Ok(self.ui_path(installed_app_id).join(name).is_file())
+    }
+
+    /// Reads a file from the UI of the app, in the first of the accepted encodings that is available for it,
+    /// returning the encoding of the content that was read
+    pub fn read_ui_file(
+        &self,
+        installed_app_id: &InstalledAppId,
+        name: &str,
+        accepted_encodings: &Vec<ContentEncoding>,
+    ) -> Result<Option<(Vec<u8>, Option<ContentEncoding>)>, FileSystemError> {
+        let ui_archive_path = self.ui_archive_path(installed_app_id);
+
+        if ui_archive_path.exists() {
+            let gzip = accepted_encodings.contains(&ContentEncoding::Gzip);
+            let file = archive_index(&ui_archive_path)?.read_file(name, gzip)?;
+
+            return Ok(
+                file.map(|(content, gzipped)| (content, gzipped.then_some(ContentEncoding::Gzip)))
+            );
+        }
+
+        let file_path = self.ui_path(installed_app_id).join(name);
+
+        for content_encoding in accepted_encodings {
+            let mut compressed_file = file_path.clone().into_os_string();
+            compressed_file.push(format!(".{}", content_encoding.extension()));
+
+            if let Ok(content) = fs::read(compressed_file) {
+                return Ok(Some((content, Some(content_encoding.clone()))));
+            }
+        }
+
+        match fs::read(file_path) {
+            Ok(content) => Ok(Some((content, None))),
+            Err(_e) => Ok(None),
+        }
+    }
+
+    pub fn ui_metadata(&self, installed_app_id: &InstalledAppId) -> crate::Result<UiMetadata> {
+        let ui_path = self.ui_path(installed_app_id);
+
+        if let Some(metadata) = UI_METADATA
+            .read()
+            .expect("Could not read the UI metadata")
+            .get(&ui_path)
+        {
+            return Ok(metadata.clone());
+        }
 
-        let ui_zip_path = self.path.join("ui.zip");
+        let generation = UI_GENERATION.load(Ordering::SeqCst);
 
-        fs::write(ui_zip_path.clone(), ui_bytes.into_owned().into_inner())?;
+        let metadata: UiMetadata =
+            match self.read_ui_file(installed_app_id, UI_METADATA_FILE, &vec![])? {
+                Some((content, _)) => serde_json::from_slice(content.as_slice())?,
+                None => UiMetadata::default(),
+            };
 
-        let file = std::fs::File::open(ui_zip_path.clone())?;
-        unzip_file(file, ui_folder_path)?;
+        let mut cached_metadata = UI_METADATA
+            .write()
+            .expect("Could not write the UI metadata");
+        if UI_GENERATION.load(Ordering::SeqCst) == generation {
+            cached_metadata.insert(ui_path, metadata.clone());
+        }
+
+        Ok(metadata)
+    }
+
+    /// Discards what has been cached from the UI, to be called whenever it's replaced or removed
+    fn forget_cached_ui(&self, installed_app_id: &InstalledAppId) {
+        UI_GENERATION.fetch_add(1, Ordering::SeqCst);
+
+        UI_METADATA
+            .write()
+            .expect("Could not write the UI metadata")
+            .remove(&self.ui_path(installed_app_id));
+        forget_archive_index(&self.ui_archive_path(installed_app_id));
+    }
+
+    pub fn remove_ui(&self, installed_app_id: &InstalledAppId) -> Result<(), FileSystemError> {
+        let ui_folder_path = self.ui_path(installed_app_id);
 
-        fs::remove_file(ui_zip_path)?;
+        if ui_folder_path.exists() {
+            fs::remove_dir_all(&ui_folder_path)?;
+        }
+
+        let ui_archive_path = self.ui_archive_path(installed_app_id);
+
+        if ui_archive_path.exists() {
+            fs::remove_file(&ui_archive_path)?;
+        }
+        self.forget_cached_ui(installed_app_id);
 
         Ok(())
     }
@@ -187,6 +501,66 @@ impl WebAppStore {
 
         Ok(())
     }
+
+    /// The app bundles of the apps installed without UI are stored next to the web-apps, by their own hash
+    fn happ_package_path(&self, app_bundle_hash: &EntryHash) -> PathBuf {
+        self.webhapp_path(app_bundle_hash).join("package.happ")
+    }
+
+    pub fn get_app_bundle(&self, app_bundle_hash: &EntryHash) -> crate::Result<Option<AppBundle>> {
+        let path = self.happ_package_path(app_bundle_hash);
+
+        if path.exists() {
+            let bytes = fs::read(path)?;
+            let app_bundle: AppBundle = mr_bundle::Bundle::decode(bytes.as_slice())?.into();
+
+            return Ok(Some(app_bundle));
+        } else {
+            return Ok(None);
+        }
+    }
+
+    pub async fn store_app_bundle(
+        &self,
+        app_bundle_hash: &EntryHash,
+        app_bundle: &AppBundle,
+    ) -> crate::Result<()> {
+        let bytes = app_bundle.encode()?;
+
+        fs::create_dir_all(self.webhapp_path(app_bundle_hash))?;
+
+        let mut file = std::fs::File::create(self.happ_package_path(app_bundle_hash))?;
+        file.write_all(bytes.as_slice())?;
+
+        Ok(())
+    }
+
+    /// Removes the stored package with the given hash, either a web-app or an app bundle
+    pub fn remove_webapp(&self, web_app_entry_hash: &EntryHash) -> crate::Result<()> {
+        let path = self.webhapp_path(web_app_entry_hash);
+
+        if path.exists() {
+            fs::remove_dir_all(path)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Computes the hash with which a web-app is stored in the [`WebAppStore`]
+pub fn web_app_hash(web_app: &WebAppBundle) -> crate::Result<EntryHash> {
+    let bytes = web_app.encode()?;
+    let hash = holo_hash::encode::blake2b_256(bytes.as_slice());
+
+    Ok(EntryHash::from_raw_32(hash))
+}
+
+/// Computes the hash with which an app bundle is stored in the [`WebAppStore`]
+pub fn app_bundle_hash(app_bundle: &AppBundle) -> crate::Result<EntryHash> {
+    let bytes = app_bundle.encode()?;
+    let hash = holo_hash::encode::blake2b_256(bytes.as_slice());
+
+    Ok(EntryHash::from_raw_32(hash))
 }
 
 pub struct IconStore {
@@ -194,19 +568,28 @@ pub struct IconStore {
 }
 
 impl IconStore {
-    fn icon_path(&self, app_entry_hash: &ActionHash) -> PathBuf {
-        self.path
-            .join(ActionHashB64::from(app_entry_hash.clone()).to_string())
+    /// The icons declared by the installed apps are kept one per app,
+    /// so that removing the icon of an app never removes the same icon of another one
+    fn app_icon_path(&self, installed_app_id: &InstalledAppId) -> PathBuf {
+        self.path.join("apps").join(installed_app_id)
     }
 
-    pub fn store_icon(&self, app_entry_hash: &ActionHash, icon_src: String) -> crate::Result<()> {
-        fs::write(self.icon_path(app_entry_hash), icon_src.as_bytes())?;
+    pub fn store_app_icon(
+        &self,
+        installed_app_id: &InstalledAppId,
+        icon_src: String,
+    ) -> crate::Result<()> {
+        let icon_path = self.app_icon_path(installed_app_id);
+        if let Some(parent) = icon_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(icon_path, icon_src.as_bytes())?;
 
         Ok(())
     }
 
-    pub fn get_icon(&self, app_entry_hash: &ActionHash) -> crate::Result<Option<String>> {
-        let icon_path = self.icon_path(app_entry_hash);
+    pub fn get_app_icon(&self, installed_app_id: &InstalledAppId) -> crate::Result<Option<String>> {
+        let icon_path = self.app_icon_path(installed_app_id);
         if icon_path.exists() {
             let icon = fs::read_to_string(icon_path)?;
             return Ok(Some(icon));
@@ -214,6 +597,277 @@ impl IconStore {
             return Ok(None);
         }
     }
+
+    pub fn remove_app_icon(&self, installed_app_id: &InstalledAppId) -> crate::Result<()> {
+        let icon_path = self.app_icon_path(installed_app_id);
+        if icon_path.exists() {
+            fs::remove_file(icon_path)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The artifacts stored in the filesystem for an installed app,
+/// which need to be cleaned up when the app is uninstalled,
+/// together with the network seed it was installed with so that others can be invited to join it,
+/// and what the [`crate::catalog`] shows about it
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct InstalledAppArtifacts {
+    pub web_app_hash: Option<EntryHashB64>,
+    /// The web-app package that was installed before the last update, kept to roll back to it
+    #[serde(default)]
+    pub previous_web_app_hash: Option<EntryHashB64>,
+    /// The app bundle of the apps installed without UI
+    #[serde(default)]
+    pub app_bundle_hash: Option<EntryHashB64>,
+    /// The app bundle that was installed before the last update, kept to roll back to it
+    #[serde(default)]
+    pub previous_app_bundle_hash: Option<EntryHashB64>,
+    #[serde(default)]
+    pub network_seed: Option<NetworkSeed>,
+    /// The name and description from the manifests of the app's bundle
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub installed_at: Option<Timestamp>,
+    #[serde(default)]
+    pub source: Option<AppSource>,
+}
+
+impl InstalledAppArtifacts {
+    /// The hashes of all the packages stored for the app, web-apps and app bundles
+    pub fn package_hashes(&self) -> Vec<EntryHashB64> {
+        [
+            &self.web_app_hash,
+            &self.previous_web_app_hash,
+            &self.app_bundle_hash,
+            &self.previous_app_bundle_hash,
+        ]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect()
+    }
+}
+
+pub struct InstalledAppsStore {
+    path: PathBuf,
+}
+
+impl InstalledAppsStore {
+    fn artifacts_path(&self, installed_app_id: &InstalledAppId) -> PathBuf {
+        self.path.join(format!("{installed_app_id}.json"))
+    }
+
+    pub fn get_artifacts(
+        &self,
+        installed_app_id: &InstalledAppId,
+    ) -> crate::Result<Option<InstalledAppArtifacts>> {
+        let path = self.artifacts_path(installed_app_id);
+
+        if path.exists() {
+            let s = fs::read_to_string(path)?;
+            let artifacts: InstalledAppArtifacts = serde_json::from_str(s.as_str())?;
+            return Ok(Some(artifacts));
+        } else {
+            return Ok(None);
+        }
+    }
+
+    pub fn store_artifacts(
+        &self,
+        installed_app_id: &InstalledAppId,
+        artifacts: &InstalledAppArtifacts,
+    ) -> crate::Result<()> {
+        let data = serde_json::to_string(artifacts)?;
+        fs::write(self.artifacts_path(installed_app_id), data.as_bytes())?;
+
+        Ok(())
+    }
+
+    pub fn remove_artifacts(&self, installed_app_id: &InstalledAppId) -> crate::Result<()> {
+        let path = self.artifacts_path(installed_app_id);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+
+        Ok(())
+    }
+
+    /// The artifacts of all the installed apps, by app id
+    pub fn list_artifacts(&self) -> crate::Result<BTreeMap<InstalledAppId, InstalledAppArtifacts>> {
+        let mut all_artifacts = BTreeMap::new();
+
+        for entry in fs::read_dir(&self.path)? {
+            let path = entry?.path();
+            if !path.extension().map(|e| e.eq("json")).unwrap_or(false) {
+                continue;
+            }
+            let Some(installed_app_id) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let s = fs::read_to_string(&path)?;
+            let artifacts: InstalledAppArtifacts = serde_json::from_str(s.as_str())?;
+            all_artifacts.insert(installed_app_id.to_string(), artifacts);
+        }
+
+        Ok(all_artifacts)
+    }
+
+    /// Whether any app other than the given one keeps the package, as its current or its previous version.
+    /// Installing the same web-app twice, or accepting an invite to it, shares its package
+    pub fn is_package_used_by_others(
+        &self,
+        package_hash: &EntryHashB64,
+        installed_app_id: &InstalledAppId,
+    ) -> crate::Result<bool> {
+        let used = self
+            .list_artifacts()?
+            .into_iter()
+            .filter(|(app_id, _)| !app_id.eq(installed_app_id))
+            .any(|(_, artifacts)| {
+                artifacts
+                    .package_hashes()
+                    .into_iter()
+                    .any(|hash| hash.eq(package_hash))
+            });
+
+        Ok(used)
+    }
+}
+
+/// The geometry and last route of an app's window, restored the next time the app is opened.
+/// Position and size are in logical pixels, and are the ones from before the window was maximized
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AppWindowState {
+    pub position: Option<(f64, f64)>,
+    pub size: Option<(f64, f64)>,
+    #[serde(default)]
+    pub maximized: bool,
+    pub route: Option<String>,
+}
+
+pub struct WindowStateStore {
+    path: PathBuf,
+}
+
+impl WindowStateStore {
+    fn window_state_path(&self, installed_app_id: &InstalledAppId) -> PathBuf {
+        self.path.join(format!("{installed_app_id}.json"))
+    }
+
+    fn open_apps_path(&self) -> PathBuf {
+        self.path.join("open-apps.json")
+    }
+
+    pub fn get_window_state(
+        &self,
+        installed_app_id: &InstalledAppId,
+    ) -> crate::Result<Option<AppWindowState>> {
+        let path = self.window_state_path(installed_app_id);
+
+        if path.exists() {
+            let s = fs::read_to_string(path)?;
+            let state: AppWindowState = serde_json::from_str(s.as_str())?;
+            return Ok(Some(state));
+        } else {
+            return Ok(None);
+        }
+    }
+
+    pub fn store_window_state(
+        &self,
+        installed_app_id: &InstalledAppId,
+        state: &AppWindowState,
+    ) -> crate::Result<()> {
+        let data = serde_json::to_string(state)?;
+        fs::write(self.window_state_path(installed_app_id), data.as_bytes())?;
+
+        Ok(())
+    }
+
+    pub fn remove_window_state(&self, installed_app_id: &InstalledAppId) -> crate::Result<()> {
+        let path = self.window_state_path(installed_app_id);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+
+        Ok(())
+    }
+
+    /// The apps that had windows open the last time the launcher was running
+    pub fn get_open_apps(&self) -> crate::Result<Vec<InstalledAppId>> {
+        let path = self.open_apps_path();
+
+        if path.exists() {
+            let s = fs::read_to_string(path)?;
+            let open_apps: Vec<InstalledAppId> = serde_json::from_str(s.as_str())?;
+            return Ok(open_apps);
+        } else {
+            return Ok(vec![]);
+        }
+    }
+
+    pub fn set_app_open(&self, installed_app_id: &InstalledAppId, open: bool) -> crate::Result<()> {
+        let mut open_apps = self.get_open_apps()?;
+        open_apps.retain(|app_id| !app_id.eq(installed_app_id));
+        if open {
+            open_apps.push(installed_app_id.clone());
+        }
+
+        let data = serde_json::to_string(&open_apps)?;
+        fs::write(self.open_apps_path(), data.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct LocaleSettings {
+    preferred_locale: Option<String>,
+}
+
+/// The locale that the user chose to use instead of the ones of the system
+pub struct LocaleStore {
+    path: PathBuf,
+}
+
+impl LocaleStore {
+    pub fn get_preferred_locale(&self) -> crate::Result<Option<String>> {
+        if self.path.exists() {
+            let s = fs::read_to_string(&self.path)?;
+            let settings: LocaleSettings = serde_json::from_str(s.as_str())?;
+            return Ok(settings.preferred_locale);
+        } else {
+            return Ok(None);
+        }
+    }
+
+    /// Setting no locale goes back to the ones of the system
+    pub fn set_preferred_locale(&self, preferred_locale: Option<String>) -> crate::Result<()> {
+        let data = serde_json::to_string(&LocaleSettings { preferred_locale })?;
+        fs::write(&self.path, data.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+/// Extracts the UI zip into the folder, going through a temporary file at `ui_zip_path`
+fn extract_ui(
+    ui_bytes: &Vec<u8>,
+    ui_zip_path: &PathBuf,
+    ui_folder_path: &PathBuf,
+) -> Result<(), FileSystemError> {
+    fs::create_dir_all(ui_folder_path)?;
+    fs::write(ui_zip_path, ui_bytes)?;
+
+    let file = std::fs::File::open(ui_zip_path)?;
+    unzip_file(file, ui_folder_path.clone())?;
+
+    precompress_text_assets(ui_folder_path)
 }
 
 pub fn unzip_file(reader: std::fs::File, outpath: PathBuf) -> Result<(), FileSystemError> {
@@ -242,6 +896,54 @@ pub fn unzip_file(reader: std::fs::File, outpath: PathBuf) -> Result<(), FileSys
     Ok(())
 }
 
+/// Below this size compressing the asset doesn't make up for the overhead of the encoding
+const MIN_PRECOMPRESSED_ASSET_SIZE: usize = 1024;
+
+/// Stores `.br` and `.gz` siblings for all the text assets in the folder,
+/// so that the http server doesn't need to compress them on every request
+pub fn precompress_text_assets(folder: &PathBuf) -> Result<(), FileSystemError> {
+    for entry in fs::read_dir(folder)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            precompress_text_assets(&path)?;
+            continue;
+        }
+
+        let is_text = mime_guess::from_path(&path)
+            .first()
+            .map(|mime| crate::assets::is_text(mime.essence_str()))
+            .unwrap_or(false);
+        if !is_text {
+            continue;
+        }
+
+        let content = fs::read(&path)?;
+        if content.len() < MIN_PRECOMPRESSED_ASSET_SIZE {
+            continue;
+        }
+
+        let mut gzip_path = path.clone().into_os_string();
+        gzip_path.push(format!(".{}", ContentEncoding::Gzip.extension()));
+        let mut encoder = flate2::write::GzEncoder::new(
+            fs::File::create(gzip_path)?,
+            flate2::Compression::best(),
+        );
+        encoder.write_all(content.as_slice())?;
+        encoder.finish()?;
+
+        let mut brotli_path = path.clone().into_os_string();
+        brotli_path.push(format!(".{}", ContentEncoding::Brotli.extension()));
+        // Quality 9 instead of the maximum 11 keeps the installation fast on low-end devices
+        let mut encoder =
+            brotli::CompressorWriter::new(fs::File::create(brotli_path)?, 4096, 9, 22);
+        encoder.write_all(content.as_slice())?;
+        encoder.flush()?;
+    }
+
+    Ok(())
+}
+
 ///On Unix systems, there is a limit to the path length of a domain socket. This function creates a symlink to
 /// the lair directory from the tempdir instead and overwrites the connectionUrl in the lair-keystore-config.yaml
 pub async fn create_and_apply_lair_symlink(keystore_data_dir: PathBuf) -> crate::Result<()> {
@@ -0,0 +1,208 @@
+use std::{collections::BTreeMap, fmt::Debug};
+
+use holochain::prelude::{CloneId, DnaHash, ExternIO, FunctionName, RoleName, ZomeName};
+use holochain_client::{AdminWebsocket, AppAgentWebsocket, AppInfo, InstalledAppId};
+use holochain_conductor_api::CellInfo;
+use hrl::Hrl;
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::RwLock;
+
+use crate::RunningHolochainInfo;
+
+/// The app, role and zome which answer a given zome function for the HRLs of a DNA
+#[derive(Clone, Debug)]
+pub struct HrlResolver {
+    pub app_id: InstalledAppId,
+    pub role_name: RoleName,
+    pub zome_name: ZomeName,
+}
+
+/// Resolvers indexed by the DNA hash of the HRL and the zome function they answer
+static HRL_RESOLVERS: RwLock<BTreeMap<(DnaHash, FunctionName), HrlResolver>> =
+    RwLock::const_new(BTreeMap::new());
+
+/// Resolvers read from the UI metadata of the apps, indexed by the DNA hash of the HRL
+static DECLARED_HRL_RESOLVERS: RwLock<BTreeMap<DnaHash, HrlResolver>> =
+    RwLock::const_new(BTreeMap::new());
+
+/// Registers a resolver explicitly, for apps that don't declare it in their UI metadata
+pub async fn register_hrl_resolver(
+    dna_hash: DnaHash,
+    fn_name: FunctionName,
+    resolver: HrlResolver,
+) {
+    HRL_RESOLVERS
+        .write()
+        .await
+        .insert((dna_hash, fn_name), resolver);
+}
+
+/// Finds the app and the role of the cell with the given DNA hash
+pub fn find_app_for_dna(apps: Vec<AppInfo>, dna_hash: &DnaHash) -> Option<(AppInfo, RoleName)> {
+    apps.into_iter().find_map(|app_info| {
+        app_info.cell_info.iter().find_map(|(role_name, cells)| {
+            cells.iter().find_map(|cell_info| match cell_info {
+                CellInfo::Provisioned(cell) => match cell.cell_id.dna_hash().eq(dna_hash) {
+                    true => Some((app_info.clone(), role_name.clone())),
+                    false => None,
+                },
+                CellInfo::Cloned(cell) => match cell.cell_id.dna_hash().eq(dna_hash) {
+                    true => Some((app_info.clone(), RoleName::from(cell.clone_id.to_string()))),
+                    false => None,
+                },
+                _ => None,
+            })
+        })
+    })
+}
+
+/// Calls the zome function that gives information about the given HRL.
+///
+/// If no resolver was registered for it, the zome declared in the UI metadata of the app
+/// that has a cell with the HRL's DNA is called, and remembered as the resolver for that DNA
+pub async fn call_hrl_resolver<I, O>(
+    info: &RunningHolochainInfo,
+    hrl: &Hrl,
+    fn_name: FunctionName,
+    input: I,
+) -> crate::Result<O>
+where
+    I: Serialize + Debug,
+    O: DeserializeOwned + Debug,
+{
+    let payload = ExternIO::encode(input)?;
+
+    let maybe_resolver = HRL_RESOLVERS
+        .read()
+        .await
+        .get(&(hrl.dna_hash.clone(), fn_name.clone()))
+        .cloned();
+
+    let resolver = match maybe_resolver {
+        Some(resolver) => resolver,
+        None => declared_hrl_resolver(info, &hrl.dna_hash).await?,
+    };
+
+    let response = call_resolver(info, &resolver, fn_name, payload).await?;
+
+    let output: O = response.decode()?;
+    Ok(output)
+}
+
+/// The app whose resolver answers for the HRLs of the DNA, if one was registered or already declared by its app
+pub(crate) async fn hrl_resolver_app_id(dna_hash: &DnaHash) -> Option<InstalledAppId> {
+    let registered_app_id = HRL_RESOLVERS
+        .read()
+        .await
+        .iter()
+        .find(|((resolver_dna_hash, _), _)| resolver_dna_hash.eq(dna_hash))
+        .map(|(_, resolver)| resolver.app_id.clone());
+
+    match registered_app_id {
+        Some(app_id) => Some(app_id),
+        None => DECLARED_HRL_RESOLVERS
+            .read()
+            .await
+            .get(dna_hash)
+            .map(|resolver| resolver.app_id.clone()),
+    }
+}
+
+/// Forgets the resolvers that the app declared, for them to be read again from its new UI metadata
+pub(crate) async fn forget_declared_hrl_resolvers(app_id: &InstalledAppId) {
+    DECLARED_HRL_RESOLVERS
+        .write()
+        .await
+        .retain(|_, resolver| !resolver.app_id.eq(app_id));
+}
+
+/// Forgets all the resolvers of the app, the registered ones included, for when it gets uninstalled
+pub(crate) async fn forget_hrl_resolvers(app_id: &InstalledAppId) {
+    HRL_RESOLVERS
+        .write()
+        .await
+        .retain(|_, resolver| !resolver.app_id.eq(app_id));
+    forget_declared_hrl_resolvers(app_id).await;
+}
+
+/// Reads the resolver for the HRLs of the DNA from the [`crate::UiMetadata`] of the app that has a cell with it
+async fn declared_hrl_resolver(
+    info: &RunningHolochainInfo,
+    dna_hash: &DnaHash,
+) -> crate::Result<HrlResolver> {
+    if let Some(resolver) = DECLARED_HRL_RESOLVERS.read().await.get(dna_hash) {
+        return Ok(resolver.clone());
+    }
+
+    let mut admin_ws = AdminWebsocket::connect(format!("ws://localhost:{}", info.admin_port))
+        .await
+        .map_err(|err| crate::Error::WebsocketConnectionError(format!("{err:?}")))?;
+
+    let apps = admin_ws
+        .list_apps(None)
+        .await
+        .map_err(|err| crate::Error::ConductorApiError(err))?;
+
+    let (app_info, role_name) = find_app_for_dna(apps, dna_hash).ok_or(
+        crate::Error::HrlResolverNotFound(format!("No app has a cell with DNA hash {dna_hash}")),
+    )?;
+    let app_id = app_info.installed_app_id;
+
+    // Clones resolve their HRLs with the same zome as the role they were cloned from
+    let base_role_name = match CloneId::try_from(role_name.clone()) {
+        Ok(clone_id) => clone_id.as_base_role_name(),
+        Err(_) => role_name.clone(),
+    };
+
+    let ui_store = info.filesystem.ui_store();
+    let ui_metadata = match ui_store.has_ui(&app_id) {
+        true => ui_store.ui_metadata(&app_id)?,
+        false => Default::default(),
+    };
+    let zome_name = ui_metadata
+        .hrl_resolvers
+        .get(&base_role_name)
+        .cloned()
+        .ok_or(crate::Error::HrlResolverNotFound(format!(
+            "App {app_id} doesn't declare the zome that resolves the HRLs of role {base_role_name}"
+        )))?;
+
+    let resolver = HrlResolver {
+        app_id,
+        role_name,
+        zome_name,
+    };
+    log::info!("Found the HRL resolver declared for DNA {dna_hash}: {resolver:?}");
+
+    DECLARED_HRL_RESOLVERS
+        .write()
+        .await
+        .insert(dna_hash.clone(), resolver.clone());
+
+    Ok(resolver)
+}
+
+async fn call_resolver(
+    info: &RunningHolochainInfo,
+    resolver: &HrlResolver,
+    fn_name: FunctionName,
+    payload: ExternIO,
+) -> crate::Result<ExternIO> {
+    let mut app_agent_ws = AppAgentWebsocket::connect(
+        format!("ws://localhost:{}", info.app_port),
+        resolver.app_id.clone(),
+        info.lair_client.clone(),
+    )
+    .await
+    .map_err(|err| crate::Error::WebsocketConnectionError(format!("{err:?}")))?;
+
+    app_agent_ws
+        .call_zome(
+            resolver.role_name.clone(),
+            resolver.zome_name.clone(),
+            fn_name,
+            payload,
+        )
+        .await
+        .map_err(|err| crate::Error::ConductorApiError(err))
+}
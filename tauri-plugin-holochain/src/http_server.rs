@@ -1,4 +1,5 @@
 use hyper::{
+    http::Request,
     service::{make_service_fn, service_fn},
     Body, Response, Server, StatusCode,
 };
@@ -6,11 +7,21 @@ use std::net::SocketAddr;
 use tauri::{AppHandle, Manager, Runtime};
 
 use crate::{
+    asset_resolvers::{asset_resolver, AssetResolver},
+    assets::{
+        accepted_encodings, asset_response, content_security_policy, error_response,
+        is_navigation_request, not_found_response, ContentEncoding,
+    },
+    dev_mode::{dev_server_url, proxy_to_dev_server},
     error::{Error, Result},
-    filesystem::FileSystem,
+    filesystem::UiMetadata,
     HolochainExt, HolochainRuntimeInfo,
 };
 
+/// The host that the window wrapper pings to check which of the protocols it can serve the UIs from,
+/// as `happ://ping` or `http://ping.<token>.localhost`
+pub const PING_HOST: &'static str = "ping";
+
 pub fn pong_iframe() -> String {
     format!("<html><head></head><body><script>window.onload = () => window.parent.postMessage('pong', '*') </script></body></html>")
 }
@@ -19,7 +30,22 @@ pub fn window_html() -> String {
     include_str!("../ui/dist/index.html").into()
 }
 
-pub fn start_http_server<R: Runtime>(app_handle: AppHandle<R>, ui_server_port: u16) -> () {
+/// Generates the secret that the app UIs need to include in their host to be served,
+/// of the form `<app_id>.<token>.localhost`, which keeps other local processes from reading them.
+/// Only lowercase alphanumeric characters are used, since hosts are case insensitive
+pub fn generate_session_token() -> String {
+    let alphabet: [char; 36] = [
+        'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r',
+        's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '0', '1', '2', '3', '4', '5', '6', '7', '8', '9',
+    ];
+    nanoid::nanoid!(32, &alphabet)
+}
+
+pub fn start_http_server<R: Runtime>(
+    app_handle: AppHandle<R>,
+    ui_server_port: u16,
+    session_token: String,
+) -> () {
     tauri::async_runtime::spawn(async move {
         let addr: SocketAddr = ([127, 0, 0, 1], ui_server_port).into();
         let app_handle = app_handle.clone();
@@ -27,6 +53,7 @@ pub fn start_http_server<R: Runtime>(app_handle: AppHandle<R>, ui_server_port: u
         // creating a 'service' to handle requests for that specific connection.
         let make_service = make_service_fn(move |_| {
             let app_handle = app_handle.clone();
+            let session_token = session_token.clone();
             // While the state was moved into the make_service closure,
             // we need to clone it here because this closure is called
             // once for every connection.
@@ -40,6 +67,7 @@ pub fn start_http_server<R: Runtime>(app_handle: AppHandle<R>, ui_server_port: u
                 let app_handle = app_handle.clone();
                 Ok::<_, hyper::Error>(service_fn(move |request| {
                     let app_handle = app_handle.clone();
+                    let session_token = session_token.clone();
                     async move {
                         let app_handle = app_handle.clone();
                         let host = request
@@ -51,17 +79,10 @@ pub fn start_http_server<R: Runtime>(app_handle: AppHandle<R>, ui_server_port: u
                             .map_err(|err| Error::HttpServerError(format!("{:?}", err)))?
                             .to_string();
 
-                        if host.starts_with("ping.localhost") {
-                            let r: Result<Response<Body>> = Ok(Response::builder()
-                                .status(202)
-                                .header("content-type", "text/html")
-                                .body(pong_iframe().into())
-                                .map_err(|err| Error::HttpServerError(format!("{:?}", err)))?);
-                            return r;
-                        }
+                        // The window wrapper is the same static page for every app, so it's not protected
                         if host.starts_with("localhost") {
                             let r: Result<Response<Body>> = Ok(Response::builder()
-                                .status(202)
+                                .status(StatusCode::OK)
                                 .header("content-type", "text/html")
                                 .body(window_html().into())
                                 .map_err(|err| Error::HttpServerError(format!("{:?}", err)))?);
@@ -72,48 +93,58 @@ pub fn start_http_server<R: Runtime>(app_handle: AppHandle<R>, ui_server_port: u
                             host.split(".").into_iter().map(|s| s.to_string()).collect();
                         let lowercase_app_id = split_host.get(0).expect("Failed to get the app id");
 
-                        let file_name = request.uri().path();
-
-                        let Ok(holochain) = app_handle.holochain() else {
+                        // Reject the request before looking up the app, so that the installed apps can't be probed
+                        if !split_host.get(1).eq(&Some(&session_token)) {
                             return Ok(Response::builder()
-                                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                                .body(
-                                    format!("Called http UI before initializing holochain")
-                                        .as_bytes()
-                                        .to_vec()
-                                        .into(),
+                                .status(StatusCode::UNAUTHORIZED)
+                                .body(Body::empty())
+                                .map_err(|err| Error::HttpServerError(format!("{:?}", err)))?);
+                        }
+
+                        if lowercase_app_id.as_str() == PING_HOST {
+                            let r: Result<Response<Body>> = Ok(Response::builder()
+                                .status(StatusCode::OK)
+                                .header("content-type", "text/html")
+                                .body(pong_iframe().into())
+                                .map_err(|err| Error::HttpServerError(format!("{:?}", err)))?);
+                            return r;
+                        }
+
+                        // The UIs in development are proxied to their dev server,
+                        // including the websockets for hot module replacement
+                        if let Some(dev_server_url) = dev_server_url(lowercase_app_id) {
+                            let response = dev_server_response(
+                                &app_handle,
+                                request,
+                                lowercase_app_id,
+                                dev_server_url,
+                            )
+                            .await;
+                            return Ok(response);
+                        }
+
+                        let asset_path = match decode_asset_path(request.uri().path()) {
+                            Ok(asset_path) => asset_path,
+                            Err(err) => {
+                                return Ok(error_response(
+                                    StatusCode::BAD_REQUEST,
+                                    err.to_string(),
                                 )
-                                .expect("Failed to return error"));
+                                .map(Body::from));
+                            }
                         };
 
-                        let r: Result<Response<Body>> = match read_asset(
-                            &holochain.filesystem,
-                            &lowercase_app_id,
-                            file_name.to_string(),
+                        let response = app_asset_response(
+                            &app_handle,
+                            &request,
+                            lowercase_app_id,
+                            asset_path,
+                            accepted_encodings(request.headers()),
                         )
-                        .await
-                        {
-                            Ok(Some((asset, mime_type))) => {
-                                let mut response_builder = Response::builder().status(202);
-                                if let Some(mime_type) = mime_type {
-                                    response_builder =
-                                        response_builder.header("content-type", mime_type);
-                                }
-
-                                Ok(response_builder
-                                    .body(asset.into())
-                                    .expect("Failed to build body of resposne"))
-                            }
-                            Ok(None) => Ok(Response::builder()
-                                .status(404)
-                                .body(vec![].into())
-                                .map_err(|err| Error::HttpServerError(format!("{:?}", err)))?),
-                            Err(e) => Ok(Response::builder()
-                                .status(500)
-                                .body(format!("{:?}", e).into())
-                                .expect("Failed to build body of error response")),
-                        };
+                        .await;
+
                         // admin_ws.close();
+                        let r: Result<Response<Body>> = Ok(response.map(Body::from));
                         r
                     }
                 }))
@@ -170,38 +201,196 @@ pub fn app_id_from_applet_id(applet_id: &String) -> String {
 //     applet_id_from_app_id(&app.installed_app_id)
 // }
 
-pub async fn read_asset(
-    fs: &FileSystem,
-    app_id: &String,
-    mut asset_name: String,
-) -> Result<Option<(Vec<u8>, Option<String>)>> {
-    // println!("Reading asset from filesystem. Asset name: {}", asset_name);
-    if asset_name.starts_with("/") {
-        asset_name = asset_name
-            .strip_prefix("/")
-            .expect("Failed to strip prefix")
-            .to_string();
+/// Parses a request of the form `happ://<app_id>/<path>`, returning the app id and the decoded path of the asset
+pub fn parse_happ_url(uri: &str) -> Result<(String, String)> {
+    let url = url::Url::parse(uri)
+        .map_err(|err| Error::MalformedAssetRequest(format!("{uri}: {err}")))?;
+
+    if url.scheme() != "happ" {
+        return Err(Error::MalformedAssetRequest(format!(
+            "{uri}: expected the happ scheme"
+        )));
     }
-    if asset_name == "" {
-        asset_name = String::from("index.html");
+
+    let app_id =
+        url.host_str()
+            .filter(|host| !host.is_empty())
+            .ok_or(Error::MalformedAssetRequest(format!(
+                "{uri}: missing app id"
+            )))?;
+
+    Ok((app_id.to_string(), decode_asset_path(url.path())?))
+}
+
+/// Percent-decodes the path of an asset request, rejecting the segments that could escape the app's UI
+pub fn decode_asset_path(path: &str) -> Result<String> {
+    let mut segments: Vec<String> = vec![];
+
+    for segment in path.split('/') {
+        let segment = percent_encoding::percent_decode_str(segment)
+            .decode_utf8()
+            .map_err(|err| Error::MalformedAssetRequest(format!("{path}: {err}")))?;
+
+        if segment.is_empty() {
+            continue;
+        }
+        if segment.eq(".")
+            || segment.eq("..")
+            || segment.contains(|c| matches!(c, '/' | '\\' | '\0'))
+        {
+            return Err(Error::MalformedAssetRequest(format!(
+                "{path}: invalid path segment"
+            )));
+        }
+
+        segments.push(segment.to_string());
     }
 
-    let assets_path = fs.ui_store().ui_path(&app_id);
-    let asset_file = assets_path.join(asset_name);
+    Ok(segments.join("/"))
+}
+
+/// Resolves the asset of the app's UI into its response, both for the http server and the `happ` protocol
+pub async fn app_asset_response<R: Runtime, B>(
+    app_handle: &AppHandle<R>,
+    request: &Request<B>,
+    app_id: &String,
+    asset_path: String,
+    accepted_encodings: Vec<ContentEncoding>,
+) -> Response<Vec<u8>> {
+    let Ok(holochain) = app_handle.holochain() else {
+        return error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Called http UI before initializing holochain"),
+        );
+    };
 
-    let mime_guess = mime_guess::from_path(asset_file.clone());
+    let resolver = asset_resolver(&holochain.filesystem, app_id).await;
+    let ui_metadata = app_ui_metadata(resolver.as_ref(), app_id).await;
+    let content_security_policy =
+        app_content_security_policy(&holochain.runtime_info, &ui_metadata);
 
-    let mime_type = match mime_guess.first() {
-        Some(mime) => Some(mime.essence_str().to_string()),
-        None => {
-            // log::info!("Could not determine MIME Type of file '{:?}'", asset_file);
-            // println!("\n### ERROR ### Could not determine MIME Type of file '{:?}'\n", asset_file);
-            None
+    let result = match resolver
+        .resolve_asset(
+            app_id,
+            asset_path.as_str(),
+            request.uri().query(),
+            &accepted_encodings,
+        )
+        .await
+    {
+        // Client-side routed apps get their index.html when navigating to a route that doesn't match any file,
+        // while the missing resources that the page fetches are still not found
+        Ok(None) if ui_metadata.spa_fallback && is_navigation_request(request.headers()) => {
+            resolver
+                .resolve_asset(app_id, "", None, &accepted_encodings)
+                .await
         }
+        result => result,
+    };
+
+    match result {
+        Ok(Some(asset)) => asset_response(request, asset, &content_security_policy),
+        Ok(None) => not_found_response(),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("{:?}", e)),
+    }
+}
+
+/// Proxies the request for the UI in development to its dev server, with the content security policy of the app
+async fn dev_server_response<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    request: Request<Body>,
+    app_id: &String,
+    dev_server_url: url::Url,
+) -> Response<Body> {
+    let Ok(holochain) = app_handle.holochain() else {
+        return error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Called http UI before initializing holochain"),
+        )
+        .map(Body::from);
     };
 
-    match std::fs::read(asset_file.clone()) {
-        Ok(asset) => Ok(Some((asset, mime_type))),
-        Err(_e) => Ok(None), // {
+    let resolver = asset_resolver(&holochain.filesystem, app_id).await;
+    let ui_metadata = app_ui_metadata(resolver.as_ref(), app_id).await;
+    let content_security_policy =
+        app_content_security_policy(&holochain.runtime_info, &ui_metadata);
+
+    match proxy_to_dev_server(request, &dev_server_url, &content_security_policy).await {
+        Ok(response) => response,
+        Err(err) => error_response(StatusCode::BAD_GATEWAY, err.to_string()).map(Body::from),
+    }
+}
+
+/// The metadata of the app's UI as given by its resolver.
+/// Falls back to the default metadata, and so to the strict default policy, if it can't be read
+async fn app_ui_metadata(resolver: &dyn AssetResolver, app_id: &String) -> UiMetadata {
+    match resolver.ui_metadata(app_id).await {
+        Ok(metadata) => metadata,
+        Err(err) => {
+            log::error!("Failed to read the UI metadata for app {app_id}: {err:?}");
+            Default::default()
+        }
+    }
+}
+
+/// The content security policy for the app's UI, relaxed as declared in its metadata
+fn app_content_security_policy(
+    runtime_info: &HolochainRuntimeInfo,
+    ui_metadata: &UiMetadata,
+) -> String {
+    content_security_policy(
+        runtime_info.app_port,
+        runtime_info.http_server_port,
+        &ui_metadata.content_security_policy,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_asset_path_normalizes() {
+        assert_eq!(decode_asset_path("/").unwrap(), "");
+        assert_eq!(decode_asset_path("/index.html").unwrap(), "index.html");
+        assert_eq!(
+            decode_asset_path("//assets//main.js").unwrap(),
+            "assets/main.js"
+        );
+        assert_eq!(
+            decode_asset_path("/assets/my%20file.js").unwrap(),
+            "assets/my file.js"
+        );
+    }
+
+    #[test]
+    fn decode_asset_path_rejects_traversal() {
+        assert!(decode_asset_path("/../secret").is_err());
+        assert!(decode_asset_path("/assets/./main.js").is_err());
+        assert!(decode_asset_path("/%2e%2e/secret").is_err());
+        assert!(decode_asset_path("/assets/%2E%2E/%2E%2E/secret").is_err());
+        assert!(decode_asset_path("/..%2fsecret").is_err());
+        assert!(decode_asset_path("/..%5csecret").is_err());
+        assert!(decode_asset_path("/index.html%00.js").is_err());
+        assert!(decode_asset_path("/%ff").is_err());
+    }
+
+    #[test]
+    fn parse_happ_url_splits_app_id_and_path() {
+        assert_eq!(
+            parse_happ_url("happ://gather/assets/main.js?v=1").unwrap(),
+            (String::from("gather"), String::from("assets/main.js"))
+        );
+        assert_eq!(
+            parse_happ_url("happ://gather").unwrap(),
+            (String::from("gather"), String::from(""))
+        );
+    }
+
+    #[test]
+    fn parse_happ_url_rejects_malformed_urls() {
+        assert!(parse_happ_url("http://gather/index.html").is_err());
+        assert!(parse_happ_url("happ:///index.html").is_err());
+        assert!(parse_happ_url("not a url").is_err());
     }
 }
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use holochain::prelude::{EntryHashB64, MembraneProof, NetworkSeed, RoleName};
+use holochain_client::InstalledAppId;
+use serde::{Deserialize, Serialize};
+
+pub const INVITE_LINK_PREFIX: &str = "happ-invite://";
+
+/// All that is needed to join the same network as the inviting app:
+/// its web-app bundle, found by hash in the [`crate::filesystem::WebAppStore`], and its network seed
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Invite {
+    pub app_id: InstalledAppId,
+    pub web_app_hash: EntryHashB64,
+    pub network_seed: Option<NetworkSeed>,
+    #[serde(default)]
+    pub membrane_proofs: HashMap<RoleName, MembraneProof>,
+}
+
+impl Invite {
+    pub fn to_link(&self) -> crate::Result<String> {
+        let bytes = serde_json::to_vec(self)?;
+
+        Ok(format!(
+            "{INVITE_LINK_PREFIX}{}",
+            URL_SAFE_NO_PAD.encode(bytes)
+        ))
+    }
+
+    pub fn from_link(link: &str) -> crate::Result<Invite> {
+        let encoded =
+            link.trim()
+                .strip_prefix(INVITE_LINK_PREFIX)
+                .ok_or(crate::Error::MalformedInvite(String::from(
+                    "Link is not an invite",
+                )))?;
+
+        let bytes = URL_SAFE_NO_PAD
+            .decode(encoded)
+            .map_err(|err| crate::Error::MalformedInvite(format!("{err:?}")))?;
+
+        let invite: Invite = serde_json::from_slice(bytes.as_slice())?;
+
+        // The app id becomes a path in the filesystem and a label of the host the UI is served from
+        if !is_valid_app_id(&invite.app_id) {
+            return Err(crate::Error::MalformedInvite(format!(
+                "Invalid app id {:?}",
+                invite.app_id
+            )));
+        }
+
+        Ok(invite)
+    }
+}
+
+/// Only lowercase ascii letters, digits, `_` and `-`
+fn is_valid_app_id(app_id: &str) -> bool {
+    !app_id.is_empty()
+        && app_id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use holochain::prelude::EntryHash;
+
+    use super::*;
+
+    fn invite() -> Invite {
+        Invite {
+            app_id: String::from("gather"),
+            web_app_hash: EntryHash::from_raw_32(vec![3; 32]).into(),
+            network_seed: Some(String::from("seed")),
+            membrane_proofs: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn invite_link_roundtrip() {
+        let link = invite().to_link().unwrap();
+        assert!(link.starts_with(INVITE_LINK_PREFIX));
+
+        let parsed = Invite::from_link(link.as_str()).unwrap();
+        assert_eq!(parsed.app_id, "gather");
+        assert_eq!(parsed.web_app_hash, invite().web_app_hash);
+        assert_eq!(parsed.network_seed, Some(String::from("seed")));
+        assert!(parsed.membrane_proofs.is_empty());
+    }
+
+    #[test]
+    fn invite_link_rejects_other_links() {
+        assert!(Invite::from_link("https://example.com").is_err());
+        assert!(Invite::from_link(format!("{INVITE_LINK_PREFIX}!!!").as_str()).is_err());
+        assert!(Invite::from_link(
+            format!("{INVITE_LINK_PREFIX}{}", URL_SAFE_NO_PAD.encode("{}")).as_str()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn invite_link_rejects_invalid_app_ids() {
+        for app_id in ["../..", "gather.app", "Gather", "", "a/b"] {
+            let link = Invite {
+                app_id: String::from(app_id),
+                ..invite()
+            }
+            .to_link()
+            .unwrap();
+
+            assert!(matches!(
+                Invite::from_link(link.as_str()),
+                Err(crate::Error::MalformedInvite(_))
+            ));
+        }
+    }
+}
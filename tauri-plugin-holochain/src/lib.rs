@@ -1,6 +1,12 @@
-use std::{collections::HashMap, path::PathBuf, time::Duration};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::PathBuf,
+    sync::Mutex,
+    time::Duration,
+};
 
-use http_server::{pong_iframe, read_asset};
+use assets::{accepted_encodings, error_response};
+use http_server::{app_asset_response, parse_happ_url, pong_iframe, PING_HOST};
 use hyper::StatusCode;
 use lair_keystore_api::LairClient;
 pub use launch::RunningHolochainInfo;
@@ -9,15 +15,17 @@ use tauri::{
     http::response,
     plugin::{Builder, TauriPlugin},
     scope::ipc::RemoteDomainAccessScope,
-    AppHandle, Manager, Runtime, Window, WindowBuilder, WindowUrl,
+    AppHandle, Manager, Runtime, Window, WindowBuilder, WindowEvent, WindowUrl,
 };
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
 
 use holochain::prelude::{
-    holochain_serial, AnyDhtHash, AppBundle, DnaHash, ExternIO, MembraneProof, NetworkSeed,
-    RoleName, SerializedBytes,
+    holochain_serial, AgentPubKeyB64, AnyDhtHash, AppBundle, DnaHash, ExternIO, FunctionName,
+    MembraneProof, NetworkSeed, RoleName, SerializedBytes,
 };
 use holochain_client::{
     AdminWebsocket, AppAgentWebsocket, AppInfo, AppWebsocket, ConductorApiError, InstallAppPayload,
+    InstalledAppId,
 };
 use holochain_conductor_api::CellInfo;
 use holochain_keystore::MetaLairClient;
@@ -29,25 +37,60 @@ mod desktop;
 #[cfg(mobile)]
 mod mobile;
 
+mod asset_resolvers;
+mod assets;
+mod catalog;
 mod commands;
 mod config;
+mod deep_link;
+mod dev_mode;
 mod error;
 mod filesystem;
+mod hrl_resolvers;
 mod http_server;
+mod invites;
 mod launch;
+mod locales;
+mod ui_archive;
 
+pub use asset_resolvers::{
+    register_asset_resolver, unregister_asset_resolver, AssetResolver, InMemoryAssetResolver,
+    ProxyAssetResolver, UiStoreAssetResolver,
+};
+pub use assets::{Asset, ContentEncoding};
+use catalog::{app_catalog, record_app_catalog, ManifestInfo};
+pub use catalog::{AppCatalogEntry, AppSource};
 use commands::install_web_app::{
-    install_app, install_web_app, update_app, update_web_app, UpdateAppError,
+    complete_web_app_installation, install_app, install_web_app, migrate_web_app, plan_app_update,
+    plan_web_app_update, prepare_web_app_installation, remove_abandoned_uis, rollback_app_bundle,
+    rollback_web_app, store_app_bundle_package, uninstall_app, update_app, update_web_app,
+    PendingInstallation,
+};
+pub use commands::install_web_app::{
+    AppUpdatePlan, CellUpdatePlan, IntegrityZomeChange, UpdateAppError,
+};
+pub use deep_link::{handle_deep_link, parse_hrl, HRL_SCHEME};
+use deep_link::{hrl_to_url, open_pending_deep_links, PendingDeepLinks};
+pub use dev_mode::{
+    disable_dev_mode, enable_dev_mode, parse_dev_bundle_arg, parse_dev_server_arg,
+    unwatch_app_bundle, watch_app_bundle,
 };
 pub use error::{Error, Result};
-use filesystem::FileSystem;
+use filesystem::{read_web_app_ui_metadata, AppWindowState, FileSystem};
+pub use filesystem::{MigrationFunctions, UiMetadata, UiStorage};
+use hrl_resolvers::hrl_resolver_app_id;
+pub use hrl_resolvers::{call_hrl_resolver, find_app_for_dna, register_hrl_resolver, HrlResolver};
+pub use invites::Invite;
 pub use launch::launch;
+pub use locales::{negotiate_locale, preferred_locale, preferred_locales, DEFAULT_LOCALE};
 
 use crate::launch::wait_until_app_ws_is_available;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct HolochainRuntimeInfo {
     http_server_port: u16,
+    /// Secret for this launch that the app UIs served by the http server need to include in their host
+    http_server_token: String,
     app_port: u16,
     admin_port: u16,
 }
@@ -58,29 +101,54 @@ pub struct HolochainPlugin<R: Runtime> {
     pub filesystem: FileSystem,
     pub runtime_info: HolochainRuntimeInfo,
     pub lair_client: LairClient,
+    /// The app that each of the open app windows belongs to, indexed by window label
+    windows_app_ids: Mutex<HashMap<String, InstalledAppId>>,
+    /// The route that the app in each window last reported, indexed by window label
+    windows_routes: Mutex<HashMap<String, String>>,
+    /// The installations waiting for their UI to provide the membrane proofs
+    pending_installations: Mutex<HashMap<InstalledAppId, PendingInstallation>>,
 }
 
 impl<R: Runtime> HolochainPlugin<R> {
+    /// The URL of the window wrapping the app's iframe, which forwards the route to the iframe
+    fn app_window_url(&self, route: Option<String>) -> Result<url::Url> {
+        let base = url::Url::parse(
+            format!("http://localhost:{}", self.runtime_info.http_server_port).as_str(),
+        )?;
+
+        let Some(route) = route else {
+            return Ok(base);
+        };
+
+        // Anything but a path, like `//example.com` or `https://example.com`, would navigate the window away from the app
+        if !route.starts_with('/') || route.starts_with("//") {
+            return Err(crate::Error::InvalidRoute(route));
+        }
+        let url = base.join(route.as_str())?;
+        if !url.origin().eq(&base.origin()) {
+            return Err(crate::Error::InvalidRoute(route));
+        }
+
+        Ok(url)
+    }
+
     fn build_window(
         &self,
         app_id: String,
         label: String,
-        query_args: Option<String>,
+        route: Option<String>,
+        window_state: Option<AppWindowState>,
     ) -> Result<Window<R>> {
-        let app_id_env_command = format!(r#"window.__APP_ID__ = "{}";"#, app_id);
-
-        let query_args = query_args.unwrap_or_default();
+        let mut app_id_env_command = format!(r#"window.__APP_ID__ = "{}";"#, app_id);
+        // The UIs in development are loaded through the http server, which proxies the websockets of their dev server
+        if dev_mode::dev_server_url(&app_id).is_some() {
+            app_id_env_command.push_str("window.__DEV_MODE__ = true;");
+        }
 
         let mut window_builder = WindowBuilder::new(
             &self.app_handle,
             label.clone(),
-            WindowUrl::External(url::Url::parse(
-                format!(
-                    "http://localhost:{}?{query_args}",
-                    self.runtime_info.http_server_port
-                )
-                .as_str(),
-            )?),
+            WindowUrl::External(self.app_window_url(route)?),
         )
         .initialization_script(app_id_env_command.as_str());
 
@@ -88,10 +156,78 @@ impl<R: Runtime> HolochainPlugin<R> {
         {
             window_builder = window_builder
                 .min_inner_size(1000.0, 800.0)
-                .title(app_id.clone());
+                .title(self.app_window_title(&app_id));
+
+            if let Some(window_state) = window_state {
+                if let Some((x, y)) = window_state.position {
+                    window_builder = window_builder.position(x, y);
+                }
+                if let Some((width, height)) = window_state.size {
+                    window_builder = window_builder.inner_size(width, height);
+                }
+                window_builder = window_builder.maximized(window_state.maximized);
+            }
         }
         let window = window_builder.build()?;
 
+        self.windows_app_ids
+            .lock()
+            .expect("Could not lock the windows app ids")
+            .insert(label.clone(), app_id.clone());
+        self.filesystem
+            .window_state_store()
+            .set_app_open(&app_id, true)?;
+
+        let app_handle = self.app_handle.clone();
+        window.on_window_event(move |event| {
+            let Ok(holochain) = app_handle.holochain() else {
+                return;
+            };
+            match event {
+                WindowEvent::CloseRequested { .. } => {
+                    // Windows of apps that have been uninstalled have nothing to save
+                    if holochain
+                        .windows_app_ids
+                        .lock()
+                        .expect("Could not lock the windows app ids")
+                        .get(&label)
+                        .is_none()
+                    {
+                        return;
+                    }
+                    if let Some(window) = app_handle.get_window(&label) {
+                        if let Err(err) = holochain.save_window_state(&window) {
+                            log::error!("Failed to save the state of window {label}: {err:?}");
+                        }
+                    }
+                    // Closing the last window of the app by hand means it's not open anymore,
+                    // while the windows that are still open when the launcher quits get reopened
+                    if holochain.app_windows(&app_id).len() <= 1 {
+                        if let Err(err) = holochain
+                            .filesystem
+                            .window_state_store()
+                            .set_app_open(&app_id, false)
+                        {
+                            log::error!("Failed to mark app {app_id} as closed: {err:?}");
+                        }
+                    }
+                }
+                WindowEvent::Destroyed => {
+                    holochain
+                        .windows_app_ids
+                        .lock()
+                        .expect("Could not lock the windows app ids")
+                        .remove(&label);
+                    holochain
+                        .windows_routes
+                        .lock()
+                        .expect("Could not lock the windows routes")
+                        .remove(&label);
+                }
+                _ => {}
+            }
+        });
+
         self.app_handle.ipc_scope().configure_remote_access(
             RemoteDomainAccessScope::new("localhost")
                 .add_window(window.label())
@@ -101,67 +237,350 @@ impl<R: Runtime> HolochainPlugin<R> {
         Ok(window)
     }
 
+    /// The title declared in the app's UI metadata in the locale that best matches the preferred ones,
+    /// or the app id if it has none
+    fn app_window_title(&self, app_id: &InstalledAppId) -> String {
+        let title = match self.filesystem.ui_store().ui_metadata(app_id) {
+            Ok(metadata) => {
+                let title_locales: Vec<String> = metadata.title.keys().cloned().collect();
+                preferred_locales(&self.filesystem)
+                    .ok()
+                    .and_then(|preferred| negotiate_locale(&preferred, &title_locales))
+                    .and_then(|locale| metadata.title.get(&locale).cloned())
+            }
+            Err(err) => {
+                log::warn!("Failed to read the UI metadata for app {app_id}: {err:?}");
+                None
+            }
+        };
+
+        title.unwrap_or(app_id.clone())
+    }
+
+    /// The locale in which the app should be shown: the one among the locales that its UI declares
+    /// that best matches the preferred ones, or just the most preferred one if it declares none
+    pub fn app_locale(&self, app_id: &InstalledAppId) -> crate::Result<String> {
+        let metadata = self.filesystem.ui_store().ui_metadata(app_id)?;
+        let preferred = preferred_locales(&self.filesystem)?;
+
+        let locale = negotiate_locale(&preferred, &metadata.locales)
+            .or(metadata.locales.first().cloned())
+            .or(preferred.first().cloned())
+            .unwrap_or(String::from(DEFAULT_LOCALE));
+
+        Ok(locale)
+    }
+
+    /// Overrides the locales of the system with the given one, or goes back to them if `None`.
+    /// Retitles the open app windows, notifies their UIs of the locale negotiated for each of their apps
+    /// and emits `locale-changed` with the new preferred locales
+    pub fn set_preferred_locale(&self, locale: Option<String>) -> crate::Result<()> {
+        self.filesystem
+            .locale_store()
+            .set_preferred_locale(locale)?;
+
+        let locales = preferred_locales(&self.filesystem)?;
+
+        let windows: Vec<(String, InstalledAppId)> = self
+            .windows_app_ids
+            .lock()
+            .expect("Could not lock the windows app ids")
+            .iter()
+            .map(|(label, app_id)| (label.clone(), app_id.clone()))
+            .collect();
+        for (label, app_id) in windows {
+            let Some(window) = self.app_handle.get_window(&label) else {
+                continue;
+            };
+            #[cfg(desktop)]
+            window.set_title(self.app_window_title(&app_id).as_str())?;
+            let locale_literal = serde_json::to_string(&self.app_locale(&app_id)?)?;
+            window.eval(
+                format!(
+                    "window.__LOCALE_CHANGED__ && window.__LOCALE_CHANGED__({locale_literal});"
+                )
+                .as_str(),
+            )?;
+        }
+
+        self.app_handle.emit("locale-changed", locales)?;
+
+        Ok(())
+    }
+
+    /// Returns the id of the app that the given window was opened for
+    pub fn window_app_id(&self, window: &Window<R>) -> crate::Result<InstalledAppId> {
+        self.windows_app_ids
+            .lock()
+            .expect("Could not lock the windows app ids")
+            .get(window.label())
+            .cloned()
+            .ok_or(crate::Error::WindowNotFromAnApp(window.label().to_string()))
+    }
+
+    /// Whether the window is one of the launcher windows given to [`init`]
+    pub fn is_launcher_window(&self, window: &Window<R>) -> bool {
+        self.app_handle
+            .state::<LauncherWindows>()
+            .0
+            .contains(window.label())
+    }
+
+    /// Only the launcher windows are authorized to act on all the apps
+    pub fn authorize_launcher_window(&self, window: &Window<R>) -> crate::Result<()> {
+        match self.is_launcher_window(window) {
+            true => Ok(()),
+            false => Err(crate::Error::NotALauncherWindow(window.label().to_string())),
+        }
+    }
+
+    /// The launcher windows are authorized to act on any app, app windows only on their own app,
+    /// and any other window on none
+    pub fn authorize_window_for_app(
+        &self,
+        window: &Window<R>,
+        app_id: &InstalledAppId,
+    ) -> crate::Result<()> {
+        if self.is_launcher_window(window) {
+            return Ok(());
+        }
+        match self.window_app_id(window) {
+            Ok(window_app_id) if window_app_id.eq(app_id) => Ok(()),
+            _ => Err(crate::Error::UnauthorizedWindow(
+                window.label().to_string(),
+                app_id.clone(),
+            )),
+        }
+    }
+
+    /// Returns all the open windows for the given app
+    fn app_windows(&self, app_id: &String) -> Vec<Window<R>> {
+        self.windows_app_ids
+            .lock()
+            .expect("Could not lock the windows app ids")
+            .iter()
+            .filter(|(_, window_app_id)| window_app_id.eq(&app_id))
+            .filter_map(|(label, _)| self.app_handle.get_window(label))
+            .collect()
+    }
+
+    /// Saves the geometry and the route of the given app window, to restore them the next time its app is opened
+    fn save_window_state(&self, window: &Window<R>) -> crate::Result<()> {
+        let app_id = self.window_app_id(window)?;
+        let store = self.filesystem.window_state_store();
+        let mut state = store.get_window_state(&app_id)?.unwrap_or_default();
+
+        #[cfg(desktop)]
+        {
+            state.maximized = window.is_maximized()?;
+
+            // Keep the geometry from before the window was maximized
+            if !state.maximized {
+                let scale_factor = window.scale_factor()?;
+                let position = window.outer_position()?.to_logical::<f64>(scale_factor);
+                let size = window.inner_size()?.to_logical::<f64>(scale_factor);
+                state.position = Some((position.x, position.y));
+                state.size = Some((size.width, size.height));
+            }
+        }
+
+        // The provisioning state can't be resumed after a restart, since pending installations are lost
+        let provisioning = self
+            .pending_installations
+            .lock()
+            .expect("Could not lock the pending installations")
+            .contains_key(&app_id);
+        // The URL of the window is the one it was opened with, only the app knows where its iframe navigated to
+        state.route = match provisioning {
+            true => None,
+            false => self
+                .windows_routes
+                .lock()
+                .expect("Could not lock the windows routes")
+                .get(window.label())
+                .cloned(),
+        };
+
+        store.store_window_state(&app_id, &state)
+    }
+
+    /// Saves the state of all the open app windows, to be called before the launcher quits
+    pub fn save_windows_state(&self) -> crate::Result<()> {
+        let labels: Vec<String> = self
+            .windows_app_ids
+            .lock()
+            .expect("Could not lock the windows app ids")
+            .keys()
+            .cloned()
+            .collect();
+
+        for label in labels {
+            if let Some(window) = self.app_handle.get_window(&label) {
+                self.save_window_state(&window)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Navigates the given app window to the route, of the form `/path?query`
+    fn navigate_window(&self, window: &Window<R>, route: String) -> crate::Result<()> {
+        let url = self.app_window_url(Some(route))?;
+        let url_literal = serde_json::to_string(url.as_str())?;
+
+        // The route that the app reported is not where it is anymore
+        self.windows_routes
+            .lock()
+            .expect("Could not lock the windows routes")
+            .remove(window.label());
+
+        window.eval(format!("window.location.assign({url_literal});").as_str())?;
+        Ok(())
+    }
+
+    /// Records the route that the app in the window navigated to, of the form `/path?query`,
+    /// to reopen the window there the next time
+    pub fn set_window_route(&self, window: &Window<R>, route: String) -> crate::Result<()> {
+        self.window_app_id(window)?;
+        // Checked now so that a bad route doesn't prevent the app from being reopened
+        self.app_window_url(Some(route.clone()))?;
+
+        self.windows_routes
+            .lock()
+            .expect("Could not lock the windows routes")
+            .insert(window.label().to_string(), route);
+        Ok(())
+    }
+
+    /// Reloads all the open windows of the app, keeping their current route
+    pub fn reload_app_windows(&self, app_id: &InstalledAppId) -> crate::Result<()> {
+        for window in self.app_windows(app_id) {
+            window.eval("window.location.reload();")?;
+        }
+        Ok(())
+    }
+
     pub async fn open_app(&self, app_id: String) -> crate::Result<()> {
+        self.open_app_window(app_id, None, false).await?;
+        Ok(())
+    }
+
+    /// Focuses an already open window for the app, navigating it to the route if given,
+    /// or opens a new window if the app has none or if `new_window` is set
+    pub async fn open_app_window(
+        &self,
+        app_id: String,
+        route: Option<String>,
+        new_window: bool,
+    ) -> crate::Result<Window<R>> {
         log::info!("Opening app {}", app_id);
 
+        if !new_window {
+            if let Some(window) = self.app_windows(&app_id).into_iter().next() {
+                if let Some(route) = route {
+                    self.navigate_window(&window, route)?;
+                }
+
+                #[cfg(desktop)]
+                {
+                    window.unminimize()?;
+                    window.show()?;
+                }
+                window.set_focus()?;
+
+                log::info!("Focused already open window for app {}", app_id);
+                return Ok(window);
+            }
+        }
+
         wait_until_app_ws_is_available(self.runtime_info.app_port).await?;
         log::info!("AppWebsocket is available");
 
-        let _window = self.build_window(app_id.clone(), app_id.clone(), None)?;
+        let label = match self.app_handle.get_window(&app_id) {
+            None => app_id.clone(),
+            Some(_) => format!("{app_id}_{}", nanoid::nanoid!(5)),
+        };
+
+        // Only restore the geometry for the first window of the app, so that new ones don't overlap it
+        let window_state = match self.app_windows(&app_id).is_empty() {
+            true => self
+                .filesystem
+                .window_state_store()
+                .get_window_state(&app_id)?,
+            false => None,
+        };
+        let route = route.or(window_state
+            .as_ref()
+            .and_then(|window_state| window_state.route.clone()));
+
+        let window = self.build_window(app_id.clone(), label, route, window_state)?;
 
         log::info!("Opened app {}", app_id);
-        Ok(())
+        Ok(window)
     }
 
     pub async fn open_hrl(&self, hrl: Hrl) -> crate::Result<()> {
         log::info!("Opening hrl {:?}", hrl);
 
-        let mut admin_ws = self.admin_websocket().await?;
+        // The app of the resolver is the one that owns the HRL, even if other apps have a cell with its DNA
+        let app_id = match hrl_resolver_app_id(&hrl.dna_hash).await {
+            Some(app_id) => app_id,
+            None => {
+                let mut admin_ws = self.admin_websocket().await?;
+
+                let apps = admin_ws
+                    .list_apps(None)
+                    .await
+                    .map_err(|err| crate::Error::ConductorApiError(err))?;
+
+                let (app_info, _) =
+                    find_app_for_dna(apps, &hrl.dna_hash).ok_or(crate::Error::OpenAppError(
+                        format!("Could not find any app for this hrl: {hrl:?}"),
+                    ))?;
+                app_info.installed_app_id
+            }
+        };
 
-        let apps = admin_ws.list_apps(None).await.expect("Failed to list apps");
-
-        let dna_hash = &hrl.dna_hash;
-
-        let app_info = apps
-            .into_iter()
-            .find_map(|app_info| {
-                app_info.cell_info.values().find_map(|cells| {
-                    cells.iter().find_map(|cell_info| match cell_info {
-                        CellInfo::Provisioned(cell) => match cell.cell_id.dna_hash().eq(dna_hash) {
-                            true => Some(app_info.clone()),
-                            false => None,
-                        },
-                        CellInfo::Cloned(cell) => match cell.cell_id.dna_hash().eq(dna_hash) {
-                            true => Some(app_info.clone()),
-                            false => None,
-                        },
-                        _ => None,
-                    })
-                })
-            })
-            .ok_or(crate::Error::OpenAppError(format!(
-                "Could not find any app for this hrl: {hrl:?}"
-            )))?;
+        let route = format!(
+            "/?hrl={}",
+            url::form_urlencoded::byte_serialize(hrl_to_url(&hrl).as_bytes()).collect::<String>()
+        );
 
-        let query_args = format!("hrl={hrl:?}");
+        self.open_app_window(app_id.clone(), Some(route.clone()), false)
+            .await?;
 
-        let uid = nanoid::nanoid!(5);
-        let label = format!("{}_{uid}", app_info.installed_app_id);
+        log::info!("Opened app {app_id} with route {route}");
+        Ok(())
+    }
 
-        // let _window = self.build_window(
-        //     app_info.installed_app_id.clone(),
-        //     label,
-        //     // Some(query_args.clone()),
-        //     None,
-        // )?;
+    /// Reopens the apps that had windows open the last time the launcher quit
+    pub async fn reopen_apps(&self) -> crate::Result<()> {
+        let open_apps = self.filesystem.window_state_store().get_open_apps()?;
+
+        for app_id in open_apps {
+            if let Err(err) = self.open_app(app_id.clone()).await {
+                log::error!("Failed to reopen app {app_id}: {err:?}");
+            }
+        }
 
-        log::info!(
-            "Opened app {} with query_args {query_args}",
-            app_info.installed_app_id
-        );
         Ok(())
     }
 
+    /// Calls the zome function that gives information about the given HRL in the app that owns it
+    pub async fn call_hrl_resolver<I, O>(
+        &self,
+        hrl: &Hrl,
+        fn_name: FunctionName,
+        input: I,
+    ) -> crate::Result<O>
+    where
+        I: Serialize + std::fmt::Debug,
+        O: serde::de::DeserializeOwned + std::fmt::Debug,
+    {
+        let info = launch().await?;
+        call_hrl_resolver(&info, hrl, fn_name, input).await
+    }
+
     pub async fn admin_websocket(&self) -> crate::Result<AdminWebsocket> {
         let admin_ws =
             AdminWebsocket::connect(format!("ws://localhost:{}", self.runtime_info.admin_port))
@@ -296,6 +715,27 @@ impl<R: Runtime> HolochainPlugin<R> {
         membrane_proofs: HashMap<RoleName, MembraneProof>,
         network_seed: Option<NetworkSeed>,
     ) -> crate::Result<AppInfo> {
+        self.install_web_app_from_source(
+            app_id,
+            web_app_bundle,
+            membrane_proofs,
+            network_seed,
+            AppSource::Bundle,
+        )
+        .await
+    }
+
+    async fn install_web_app_from_source(
+        &self,
+        app_id: String,
+        web_app_bundle: WebAppBundle,
+        membrane_proofs: HashMap<RoleName, MembraneProof>,
+        network_seed: Option<NetworkSeed>,
+        source: AppSource,
+    ) -> crate::Result<AppInfo> {
+        self.confirm_content_security_policy_relaxations(&app_id, &web_app_bundle, None)
+            .await?;
+
         let mut admin_ws = self.admin_websocket().await?;
         let app_info = install_web_app(
             &mut admin_ws,
@@ -304,6 +744,7 @@ impl<R: Runtime> HolochainPlugin<R> {
             web_app_bundle,
             membrane_proofs,
             network_seed,
+            source,
         )
         .await?;
 
@@ -320,22 +761,266 @@ impl<R: Runtime> HolochainPlugin<R> {
         membrane_proofs: HashMap<RoleName, MembraneProof>,
         network_seed: Option<NetworkSeed>,
     ) -> crate::Result<AppInfo> {
+        let manifest_info = ManifestInfo::from_app_manifest(app_bundle.manifest());
+
         let mut admin_ws = self.admin_websocket().await?;
         let app_info = install_app(
             &mut admin_ws,
             app_id.clone(),
-            app_bundle,
+            app_bundle.clone(),
             membrane_proofs,
             network_seed,
         )
         .await?;
+        store_app_bundle_package(&self.filesystem, &app_id, &app_bundle).await?;
+        record_app_catalog(
+            &self.filesystem,
+            &app_id,
+            manifest_info,
+            Some(AppSource::Bundle),
+        )?;
+
+        self.workaround_join_failed(app_info.clone()).await?;
+
+        self.app_handle.emit("app-installed", app_id)?;
+        Ok(app_info)
+    }
+
+    /// Reads the metadata of the web-app's UI, to show the user the content security policy relaxations
+    /// that it requests before installing it
+    pub async fn web_app_ui_metadata(
+        &self,
+        web_app_bundle: &WebAppBundle,
+    ) -> crate::Result<UiMetadata> {
+        read_web_app_ui_metadata(web_app_bundle).await
+    }
+
+    /// Asks the user whether to install the web-app if its UI relaxes the content security policy,
+    /// listing the sources that it would be allowed to reach.
+    /// When it replaces the UI of `installed_app_id`, only the relaxations that the installed UI doesn't already have are confirmed
+    async fn confirm_content_security_policy_relaxations(
+        &self,
+        app_id: &InstalledAppId,
+        web_app_bundle: &WebAppBundle,
+        installed_app_id: Option<&InstalledAppId>,
+    ) -> crate::Result<()> {
+        let relaxations = self
+            .web_app_ui_metadata(web_app_bundle)
+            .await?
+            .content_security_policy;
+        let installed_relaxations = match installed_app_id {
+            Some(installed_app_id) => {
+                self.filesystem
+                    .ui_store()
+                    .ui_metadata(installed_app_id)?
+                    .content_security_policy
+            }
+            None => BTreeMap::new(),
+        };
+
+        let new_relaxations: BTreeMap<&String, Vec<&str>> = relaxations
+            .iter()
+            .map(|(directive, sources)| {
+                let new_sources = sources
+                    .iter()
+                    .filter(|source| {
+                        !installed_relaxations
+                            .get(directive)
+                            .is_some_and(|installed_sources| installed_sources.contains(source))
+                    })
+                    .map(|source| source.as_str())
+                    .collect::<Vec<&str>>();
+                (directive, new_sources)
+            })
+            .filter(|(_, new_sources)| !new_sources.is_empty())
+            .collect();
+
+        if new_relaxations.is_empty() {
+            return Ok(());
+        }
+
+        let sources = new_relaxations
+            .iter()
+            .map(|(directive, sources)| format!("{directive} {}", sources.join(" ")))
+            .collect::<Vec<String>>()
+            .join("\n");
+        let (action, verb) = match installed_app_id {
+            Some(_) => ("Update", "update"),
+            None => ("Install", "install"),
+        };
+
+        let (sender, receiver) = futures::channel::oneshot::channel();
+        self.app_handle
+            .dialog()
+            .message(format!(
+                "The app {app_id} asks to load content from outside of it:\n\n{sources}\n\nDo you want to {verb} it?"
+            ))
+            .title(format!("{action} app"))
+            .kind(MessageDialogKind::Warning)
+            .ok_button_label(action)
+            .cancel_button_label("Cancel")
+            .show(move |confirmed| {
+                let _ = sender.send(confirmed);
+            });
+
+        match receiver.await {
+            Ok(true) => Ok(()),
+            _ => Err(crate::Error::ContentSecurityPolicyDeclined(app_id.clone())),
+        }
+    }
+
+    /// Installs the web-app deferring the membrane proofs:
+    /// generates the agent key and opens the app's UI in the provisioning state,
+    /// with the agent key in its query args so that it can fetch or construct the membrane proofs.
+    /// The installation is completed when the UI calls `provide_membrane_proofs`
+    pub async fn install_web_app_with_deferred_membrane_proofs(
+        &self,
+        app_id: String,
+        web_app_bundle: WebAppBundle,
+        network_seed: Option<NetworkSeed>,
+    ) -> crate::Result<()> {
+        self.confirm_content_security_policy_relaxations(&app_id, &web_app_bundle, None)
+            .await?;
+
+        let mut admin_ws = self.admin_websocket().await?;
+        let pending_installation = prepare_web_app_installation(
+            &mut admin_ws,
+            &self.filesystem,
+            app_id.clone(),
+            web_app_bundle,
+            network_seed,
+        )
+        .await?;
+
+        let route = format!(
+            "/?provisioning=true&agent_pub_key={}",
+            AgentPubKeyB64::from(pending_installation.agent_key.clone())
+        );
+
+        self.pending_installations
+            .lock()
+            .expect("Could not lock the pending installations")
+            .insert(app_id.clone(), pending_installation);
+
+        self.open_app_window(app_id.clone(), Some(route), false)
+            .await?;
+
+        self.app_handle.emit("app-provisioning", app_id)?;
+
+        Ok(())
+    }
+
+    /// Completes a pending installation with the given membrane proofs and enables the app
+    pub async fn provide_membrane_proofs(
+        &self,
+        app_id: String,
+        membrane_proofs: HashMap<RoleName, MembraneProof>,
+    ) -> crate::Result<AppInfo> {
+        let pending_installation = self
+            .pending_installations
+            .lock()
+            .expect("Could not lock the pending installations")
+            .get(&app_id)
+            .cloned()
+            .ok_or(crate::Error::NoPendingInstallation(app_id.clone()))?;
+
+        let mut admin_ws = self.admin_websocket().await?;
+        let app_info = complete_web_app_installation(
+            &mut admin_ws,
+            &self.filesystem,
+            app_id.clone(),
+            pending_installation,
+            membrane_proofs,
+        )
+        .await?;
+
+        // Only discard the pending installation once it succeeded, so that the UI can retry
+        self.pending_installations
+            .lock()
+            .expect("Could not lock the pending installations")
+            .remove(&app_id);
 
         self.workaround_join_failed(app_info.clone()).await?;
 
+        // Reload the UI out of the provisioning state
+        for window in self.app_windows(&app_id) {
+            self.navigate_window(&window, String::from("/"))?;
+        }
+
         self.app_handle.emit("app-installed", app_id)?;
+
         Ok(app_info)
     }
 
+    /// Creates an invite link to join the same network as the given web-app
+    pub fn create_invite(
+        &self,
+        app_id: String,
+        membrane_proofs: HashMap<RoleName, MembraneProof>,
+    ) -> crate::Result<String> {
+        let artifacts = self
+            .filesystem
+            .installed_apps_store()
+            .get_artifacts(&app_id)?
+            .ok_or(crate::Error::InviteError(format!(
+                "App {app_id} was not installed as a web-app"
+            )))?;
+
+        let web_app_hash = artifacts
+            .web_app_hash
+            .ok_or(crate::Error::InviteError(format!(
+                "No web-app package was stored for app {app_id}"
+            )))?;
+
+        let invite = Invite {
+            app_id,
+            web_app_hash,
+            network_seed: artifacts.network_seed,
+            membrane_proofs,
+        };
+
+        invite.to_link()
+    }
+
+    /// Installs the web-app of the invite from the [`filesystem::WebAppStore`] with the invite's network seed
+    pub async fn accept_invite(&self, link: String) -> crate::Result<AppInfo> {
+        let invite = Invite::from_link(link.as_str())?;
+
+        let web_app_bundle = self
+            .filesystem
+            .webapp_store()
+            .get_webapp(&invite.web_app_hash.clone().into())?
+            .ok_or(crate::Error::InviteError(format!(
+                "The web-app {} for this invite is not available locally",
+                invite.web_app_hash
+            )))?;
+
+        let mut admin_ws = self.admin_websocket().await?;
+        let apps = admin_ws
+            .list_apps(None)
+            .await
+            .map_err(|err| crate::Error::ConductorApiError(err))?;
+
+        let app_id = match apps
+            .iter()
+            .any(|app| app.installed_app_id.eq(&invite.app_id))
+        {
+            true => format!("{}-{}", invite.app_id, nanoid::nanoid!(5)),
+            false => invite.app_id.clone(),
+        };
+
+        log::info!("Accepting invite to join app {} as {app_id}", invite.app_id);
+
+        self.install_web_app_from_source(
+            app_id,
+            web_app_bundle,
+            invite.membrane_proofs,
+            invite.network_seed,
+            AppSource::Invite,
+        )
+        .await
+    }
+
     pub async fn update_web_app(
         &self,
         app_id: String,
@@ -345,6 +1030,14 @@ impl<R: Runtime> HolochainPlugin<R> {
             .admin_websocket()
             .await
             .map_err(|err| UpdateAppError::WebsocketError)?;
+        self.confirm_content_security_policy_relaxations(&app_id, &web_app_bundle, Some(&app_id))
+            .await
+            .map_err(|err| match err {
+                crate::Error::ContentSecurityPolicyDeclined(app_id) => {
+                    UpdateAppError::ContentSecurityPolicyDeclined(app_id)
+                }
+                err => UpdateAppError::ReadWebAppError(format!("{err:?}")),
+            })?;
         let app_info = update_web_app(
             &mut admin_ws,
             &self.filesystem,
@@ -367,11 +1060,198 @@ impl<R: Runtime> HolochainPlugin<R> {
             .admin_websocket()
             .await
             .map_err(|err| UpdateAppError::WebsocketError)?;
-        let app_info = update_app(&mut admin_ws, app_id.clone(), app_bundle).await?;
+        let manifest_info = ManifestInfo::from_app_manifest(app_bundle.manifest());
+        let app_info = update_app(&mut admin_ws, app_id.clone(), app_bundle.clone()).await?;
+        store_app_bundle_package(&self.filesystem, &app_id, &app_bundle)
+            .await
+            .map_err(|err| UpdateAppError::StoreWebAppError(format!("{err:?}")))?;
+        record_app_catalog(&self.filesystem, &app_id, manifest_info, None)
+            .map_err(|err| UpdateAppError::StoreWebAppError(format!("{err:?}")))?;
 
         self.app_handle.emit("app-updated", app_id)?;
         Ok(app_info)
     }
+
+    /// Reverts the app to the version that was installed before its last update,
+    /// restoring its coordinator zomes and, for web-apps, its UI, and reloads its open windows
+    pub async fn rollback_app(&self, app_id: InstalledAppId) -> crate::Result<()> {
+        let is_web_app = self
+            .filesystem
+            .installed_apps_store()
+            .get_artifacts(&app_id)?
+            .map(|artifacts| artifacts.previous_web_app_hash.is_some())
+            .unwrap_or(false);
+
+        let mut admin_ws = self.admin_websocket().await?;
+        let result = match is_web_app {
+            true => rollback_web_app(&mut admin_ws, &self.filesystem, app_id.clone()).await,
+            false => rollback_app_bundle(&mut admin_ws, &self.filesystem, app_id.clone()).await,
+        };
+        result.map_err(|err| crate::Error::RollbackError(err.to_string()))?;
+
+        self.reload_app_windows(&app_id)?;
+        self.app_handle.emit("app-updated", app_id)?;
+
+        Ok(())
+    }
+
+    /// Computes what [`Self::update_web_app`] would do with the bundle, without changing the app
+    pub async fn plan_web_app_update(
+        &self,
+        app_id: String,
+        web_app_bundle: WebAppBundle,
+    ) -> std::result::Result<AppUpdatePlan, UpdateAppError> {
+        let mut admin_ws = self
+            .admin_websocket()
+            .await
+            .map_err(|_err| UpdateAppError::WebsocketError)?;
+
+        plan_web_app_update(&mut admin_ws, &self.filesystem, app_id, web_app_bundle).await
+    }
+
+    /// Computes what [`Self::update_app`] would do with the bundle, without changing the app
+    pub async fn plan_app_update(
+        &self,
+        app_id: String,
+        app_bundle: AppBundle,
+    ) -> std::result::Result<AppUpdatePlan, UpdateAppError> {
+        let mut admin_ws = self
+            .admin_websocket()
+            .await
+            .map_err(|_err| UpdateAppError::WebsocketError)?;
+
+        plan_app_update(&mut admin_ws, app_id, app_bundle).await
+    }
+
+    /// Migrates the app to the new version of its web-app, for when [`Self::update_web_app`] fails with
+    /// [`UpdateAppError::IntegrityZomesChanged`]: the new version is installed side by side as `new_app_id`
+    /// with the same agent key, and the data is handed over through the [`MigrationFunctions`] declared in its UI metadata.
+    /// If the hand over fails the new app is uninstalled again, and the old app is always left installed,
+    /// for the caller to uninstall it once the new one is working
+    pub async fn migrate_web_app(
+        &self,
+        app_id: InstalledAppId,
+        new_app_id: InstalledAppId,
+        web_app_bundle: WebAppBundle,
+        membrane_proofs: HashMap<RoleName, MembraneProof>,
+    ) -> crate::Result<AppInfo> {
+        let metadata = read_web_app_ui_metadata(&web_app_bundle).await?;
+        self.confirm_content_security_policy_relaxations(
+            &new_app_id,
+            &web_app_bundle,
+            Some(&app_id),
+        )
+        .await?;
+
+        let mut admin_ws = self.admin_websocket().await?;
+        let app_info = migrate_web_app(
+            &mut admin_ws,
+            &self.filesystem,
+            app_id.clone(),
+            new_app_id.clone(),
+            web_app_bundle,
+            membrane_proofs,
+        )
+        .await?;
+
+        let handed_over = match self.workaround_join_failed(app_info.clone()).await {
+            Ok(()) => {
+                self.hand_over_data(&app_id, &new_app_id, &metadata.migration)
+                    .await
+            }
+            Err(err) => Err(err),
+        };
+        if let Err(err) = handed_over {
+            log::error!("Failed to hand over the data from {app_id} to {new_app_id}, uninstalling it: {err:?}");
+            if let Err(uninstall_err) =
+                uninstall_app(&mut admin_ws, &self.filesystem, new_app_id.clone()).await
+            {
+                log::error!("Failed to uninstall app {new_app_id}: {uninstall_err:?}");
+            }
+            return Err(err);
+        }
+
+        self.app_handle.emit("app-installed", new_app_id.clone())?;
+        self.app_handle
+            .emit("app-migrated", (app_id.clone(), new_app_id.clone()))?;
+        log::info!("Migrated app {app_id} to {new_app_id}");
+
+        Ok(app_info)
+    }
+
+    /// Calls the export function of each role in the old app and passes its result to the import function in the new one
+    async fn hand_over_data(
+        &self,
+        from_app_id: &InstalledAppId,
+        to_app_id: &InstalledAppId,
+        migration: &BTreeMap<RoleName, MigrationFunctions>,
+    ) -> crate::Result<()> {
+        if migration.is_empty() {
+            log::warn!("App {to_app_id} declares no migration functions, no data was handed over from {from_app_id}");
+            return Ok(());
+        }
+
+        let mut from_ws = self.app_agent_websocket(from_app_id.clone()).await?;
+        let mut to_ws = self.app_agent_websocket(to_app_id.clone()).await?;
+
+        for (role_name, functions) in migration {
+            log::info!(
+                "Handing over the data of role {role_name} from {from_app_id} to {to_app_id}"
+            );
+
+            let data = from_ws
+                .call_zome(
+                    role_name.clone().into(),
+                    functions.zome_name.clone(),
+                    functions.export_fn_name.clone(),
+                    ExternIO::encode(())?,
+                )
+                .await
+                .map_err(|err| crate::Error::ConductorApiError(err))?;
+
+            to_ws
+                .call_zome(
+                    role_name.clone().into(),
+                    functions.zome_name.clone(),
+                    functions.import_fn_name.clone(),
+                    data,
+                )
+                .await
+                .map_err(|err| crate::Error::ConductorApiError(err))?;
+        }
+
+        Ok(())
+    }
+
+    /// The installed apps with the information that the launcher shows about them,
+    /// like their name, icon and version in the preferred locale
+    pub async fn list_installed_apps(&self) -> crate::Result<Vec<AppCatalogEntry>> {
+        let mut admin_ws = self.admin_websocket().await?;
+        let apps = admin_ws
+            .list_apps(None)
+            .await
+            .map_err(|err| crate::Error::ConductorApiError(err))?;
+
+        app_catalog(&self.filesystem, apps)
+    }
+
+    /// Uninstalls the app from the conductor, closes its windows and removes all its artifacts from the filesystem
+    pub async fn uninstall_app(&self, app_id: String) -> crate::Result<()> {
+        let mut admin_ws = self.admin_websocket().await?;
+        uninstall_app(&mut admin_ws, &self.filesystem, app_id.clone()).await?;
+
+        // Only close the windows once the app is gone, and without saving their state again
+        for window in self.app_windows(&app_id) {
+            self.windows_app_ids
+                .lock()
+                .expect("Could not lock the windows app ids")
+                .remove(window.label());
+            window.close()?;
+        }
+
+        self.app_handle.emit("app-uninstalled", app_id)?;
+        Ok(())
+    }
 }
 
 // Extensions to [`tauri::App`], [`tauri::AppHandle`] and [`tauri::Window`] to access the holochain APIs.
@@ -389,108 +1269,87 @@ impl<R: Runtime, T: Manager<R>> crate::HolochainExt<R> for T {
     }
 }
 
+/// The labels of the windows of the launcher itself, as opposed to the app windows
+struct LauncherWindows(BTreeSet<String>);
+
+/// The configuration of the plugin, under `plugins > holochain` in `tauri.conf.json`
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginConfig {
+    /// How the UIs of the installed apps are stored, `"extracted"` unless set to `"archive"`
+    #[serde(default)]
+    pub ui_storage: UiStorage,
+}
+
 /// Initializes the plugin.
-pub fn init<R: Runtime>(subfolder: PathBuf) -> TauriPlugin<R> {
-    Builder::new("holochain")
+/// Only the windows with the given labels are authorized to act on all the apps, like installing them
+pub fn init<R: Runtime>(subfolder: PathBuf, launcher_window_labels: Vec<String>) -> TauriPlugin<R> {
+    Builder::<R, Option<PluginConfig>>::new("holochain")
+        .setup(move |app_handle, api| {
+            app_handle.manage(api.config().clone().unwrap_or_default());
+            app_handle.manage(PendingDeepLinks::default());
+            app_handle.manage(LauncherWindows(
+                launcher_window_labels.into_iter().collect(),
+            ));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             commands::sign_zome_call::sign_zome_call,
             commands::get_locales::get_locales,
+            commands::get_locales::set_preferred_locale,
             commands::open_app::open_app,
+            commands::open_app::set_window_route,
             commands::list_apps::list_apps,
+            commands::list_apps::list_installed_apps,
             commands::get_runtime_info::get_runtime_info,
-            commands::get_runtime_info::is_holochain_ready
+            commands::get_runtime_info::is_holochain_ready,
+            commands::clone_cells::create_clone_cell,
+            commands::clone_cells::enable_clone_cell,
+            commands::clone_cells::disable_clone_cell,
+            commands::clone_cells::delete_clone_cell,
+            commands::provide_membrane_proofs::install_web_app_with_deferred_membrane_proofs,
+            commands::provide_membrane_proofs::provide_membrane_proofs,
+            commands::invites::create_invite,
+            commands::invites::accept_invite,
+            commands::rollback_app::rollback_app,
+            commands::migrate_app::migrate_app
         ])
-        .register_uri_scheme_protocol("happ", |app_handle, request| {
+        .register_asynchronous_uri_scheme_protocol("happ", |app_handle, request, responder| {
             log::info!("Received request {}", request.uri().to_string());
-            if request.uri().to_string().starts_with("happ://ping") {
-                return response::Builder::new()
-                    .status(StatusCode::ACCEPTED)
-                    .header("Content-Type", "text/html;charset=utf-8")
-                    .body(pong_iframe().as_bytes().to_vec())
-                    .expect("Failed to build body of accepted response");
-            }
-            // prepare our response
-            tauri::async_runtime::block_on(async move {
-                // let mutex = app_handle.state::<Mutex<AdminWebsocket>>();
-                // let mut admin_ws = mutex.lock().await;
-
-                let uri_without_protocol = request
-                    .uri()
-                    .to_string()
-                    .split("://")
-                    .map(|s| s.to_string())
-                    .collect::<Vec<String>>()
-                    .get(1)
-                    .expect("Malformed request: not enough items")
-                    .clone();
-                let uri_without_querystring: String = uri_without_protocol
-                    .split("?")
-                    .map(|s| s.to_string())
-                    .collect::<Vec<String>>()
-                    .get(0)
-                    .expect("Malformed request: not enough items 2")
-                    .clone();
-                let uri_components: Vec<String> = uri_without_querystring
-                    .split("/")
-                    .map(|s| s.to_string())
-                    .collect();
-                let lowercase_app_id = uri_components
-                    .get(0)
-                    .expect("Malformed request: not enough items 3");
-                let mut asset_file = PathBuf::new();
-                for i in 1..uri_components.len() {
-                    asset_file = asset_file.join(uri_components[i].clone());
+            let (app_id, asset_path) = match parse_happ_url(request.uri().to_string().as_str()) {
+                Ok(parsed) => parsed,
+                Err(err) => {
+                    responder.respond(error_response(StatusCode::BAD_REQUEST, err.to_string()));
+                    return;
                 }
+            };
+
+            if app_id.as_str() == PING_HOST {
+                responder.respond(
+                    response::Builder::new()
+                        .status(StatusCode::OK)
+                        .header("Content-Type", "text/html;charset=utf-8")
+                        .body(pong_iframe().as_bytes().to_vec())
+                        .expect("Failed to build body of accepted response"),
+                );
+                return;
+            }
 
-                let Ok(holochain) = app_handle.holochain() else {
-                    return response::Builder::new()
-                        .status(StatusCode::INTERNAL_SERVER_ERROR)
-                        .body(
-                            format!("Called http UI before initializing holochain")
-                                .as_bytes()
-                                .to_vec(),
-                        )
-                        .expect("Failed to build asset with not internal server error");
-                };
-
-                let r = match read_asset(
-                    &holochain.filesystem,
-                    lowercase_app_id,
-                    asset_file
-                        .as_os_str()
-                        .to_str()
-                        .expect("Malformed request: not enough items 4")
-                        .to_string(),
+            let app_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                // Only the webviews that declare that they decode the custom protocol responses
+                // get the precompressed variants
+                let response = app_asset_response(
+                    &app_handle,
+                    &request,
+                    &app_id,
+                    asset_path,
+                    accepted_encodings(request.headers()),
                 )
-                .await
-                {
-                    Ok(Some((asset, mime_type))) => {
-                        log::info!("Got asset for app with id: {}", lowercase_app_id);
-                        let mut response = response::Builder::new().status(StatusCode::ACCEPTED);
-                        if let Some(mime_type) = mime_type {
-                            response = response
-                                .header("Content-Type", format!("{};charset=utf-8", mime_type))
-                        } else {
-                            response = response.header("Content-Type", "charset=utf-8")
-                        }
+                .await;
 
-                        return response
-                            .body(asset)
-                            .expect("Failed to build response with asset");
-                    }
-                    Ok(None) => response::Builder::new()
-                        .status(StatusCode::NOT_FOUND)
-                        .body(vec![])
-                        .expect("Failed to build asset with not found"),
-                    Err(e) => response::Builder::new()
-                        .status(StatusCode::INTERNAL_SERVER_ERROR)
-                        .body(format!("{:?}", e).as_bytes().to_vec())
-                        .expect("Failed to build asset with not internal server error"),
-                };
-
-                // admin_ws.close();
-                r
-            })
+                responder.respond(response);
+            });
         })
         .build()
 }
@@ -516,23 +1375,53 @@ pub async fn setup_holochain<R: Runtime>(app_handle: AppHandle<R>) -> crate::Res
         filesystem,
     } = launch().await?;
 
+    // Holochain may have been launched before the plugin was set up, so its configuration applies here
+    let filesystem = FileSystem {
+        ui_storage: app_handle.state::<PluginConfig>().ui_storage,
+        ..filesystem
+    };
+
     log::info!("Starting http server at port {http_server_port:?}");
 
-    http_server::start_http_server(app_handle.clone(), http_server_port);
+    let http_server_token = http_server::generate_session_token();
+    http_server::start_http_server(
+        app_handle.clone(),
+        http_server_port,
+        http_server_token.clone(),
+    );
 
     let p = HolochainPlugin::<R> {
         app_handle: app_handle.clone(),
         lair_client,
         runtime_info: HolochainRuntimeInfo {
             http_server_port,
+            http_server_token,
             app_port,
             admin_port,
         },
         filesystem,
+        windows_app_ids: Mutex::new(HashMap::new()),
+        windows_routes: Mutex::new(HashMap::new()),
+        pending_installations: Mutex::new(HashMap::new()),
     };
 
+    // Done before the plugin is managed, so that no new installation can be pending yet
+    let mut admin_ws = p.admin_websocket().await?;
+    if let Err(err) = remove_abandoned_uis(&mut admin_ws, &p.filesystem).await {
+        log::error!("Failed to remove the UIs of the abandoned installations: {err:?}");
+    }
+
     // manage state so it is accessible by the commands
-    app_handle.manage(p);
+    {
+        // Hold the lock so that no deep link gets queued after the pending ones are opened
+        let pending_deep_links = app_handle.state::<PendingDeepLinks>();
+        let _lock = pending_deep_links
+            .0
+            .lock()
+            .expect("Could not lock the pending deep links");
+        app_handle.manage(p);
+    }
+    open_pending_deep_links(&app_handle);
 
     app_handle.emit("holochain-ready", ())?;
 
@@ -0,0 +1,104 @@
+use sys_locale::get_locales;
+
+use crate::filesystem::FileSystem;
+
+/// Used when neither the user nor the system have any locale
+pub const DEFAULT_LOCALE: &'static str = "en";
+
+/// The locales of the user by order of preference:
+/// the one they chose in the launcher, followed by the ones of the system
+pub fn preferred_locales(fs: &FileSystem) -> crate::Result<Vec<String>> {
+    let mut locales: Vec<String> = get_locales().collect();
+
+    if let Some(preferred_locale) = fs.locale_store().get_preferred_locale()? {
+        locales.retain(|locale| !same_locale(locale, &preferred_locale));
+        locales.insert(0, preferred_locale);
+    }
+
+    Ok(locales)
+}
+
+/// The locale the user prefers the most, for the places where the supported locales are unknown
+pub fn preferred_locale(fs: &FileSystem) -> crate::Result<String> {
+    Ok(preferred_locales(fs)?
+        .into_iter()
+        .next()
+        .unwrap_or(String::from(DEFAULT_LOCALE)))
+}
+
+/// Picks the supported locale that best matches the preferred ones.
+/// Each preferred locale is matched exactly first, like `sv-SE` with `sv-SE`,
+/// and then by language, like `sv-SE` with `sv`, before going on to the next preferred one
+pub fn negotiate_locale(preferred: &Vec<String>, supported: &Vec<String>) -> Option<String> {
+    preferred.iter().find_map(|preferred_locale| {
+        supported
+            .iter()
+            .find(|locale| same_locale(locale, preferred_locale))
+            .or_else(|| {
+                supported.iter().find(|locale| {
+                    language(locale).eq_ignore_ascii_case(language(preferred_locale))
+                })
+            })
+            .cloned()
+    })
+}
+
+/// Systems separate the region with either `-` or `_`
+fn same_locale(a: &str, b: &str) -> bool {
+    a.replace('_', "-")
+        .eq_ignore_ascii_case(b.replace('_', "-").as_str())
+}
+
+fn language(locale: &str) -> &str {
+    locale.split(['-', '_']).next().unwrap_or(locale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locales(locales: &[&str]) -> Vec<String> {
+        locales.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn exact_match_first() {
+        assert_eq!(
+            negotiate_locale(&locales(&["sv-SE"]), &locales(&["sv", "sv-SE"])),
+            Some(String::from("sv-SE"))
+        );
+        assert_eq!(
+            negotiate_locale(&locales(&["sv_SE"]), &locales(&["sv", "sv-se"])),
+            Some(String::from("sv-se"))
+        );
+    }
+
+    #[test]
+    fn language_match() {
+        assert_eq!(
+            negotiate_locale(&locales(&["sv-SE"]), &locales(&["en", "sv"])),
+            Some(String::from("sv"))
+        );
+        assert_eq!(
+            negotiate_locale(&locales(&["sv"]), &locales(&["en", "sv-FI"])),
+            Some(String::from("sv-FI"))
+        );
+    }
+
+    #[test]
+    fn preference_order_wins_over_exactness() {
+        assert_eq!(
+            negotiate_locale(&locales(&["sv-SE", "en-US"]), &locales(&["en-US", "sv"])),
+            Some(String::from("sv"))
+        );
+    }
+
+    #[test]
+    fn no_match() {
+        assert_eq!(
+            negotiate_locale(&locales(&["fr"]), &locales(&["en", "sv"])),
+            None
+        );
+        assert_eq!(negotiate_locale(&locales(&[]), &locales(&["en"])), None);
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::{atomic::Ordering, Arc, RwLock},
+};
+
+use zip::{CompressionMethod, ZipArchive};
+
+use crate::filesystem::{FileSystemError, UI_GENERATION};
+
+/// Location of a file inside the UI zip, as read from its central directory
+#[derive(Clone, Debug)]
+struct ArchiveEntry {
+    data_start: u64,
+    compressed_size: u64,
+    size: u64,
+    crc32: u32,
+    compression: CompressionMethod,
+}
+
+/// Index of the files in a stored UI zip, so that they can be read
+/// without parsing the central directory of the archive on every request
+pub struct UiArchiveIndex {
+    archive_path: PathBuf,
+    entries: HashMap<String, ArchiveEntry>,
+}
+
+/// The indexes of the UI zips that have already been read, by path of the zip
+static UI_ARCHIVE_INDEXES: RwLock<BTreeMap<PathBuf, Arc<UiArchiveIndex>>> =
+    RwLock::new(BTreeMap::new());
+
+/// Returns the index for the UI zip, building it from its central directory the first time
+pub fn archive_index(archive_path: &Path) -> Result<Arc<UiArchiveIndex>, FileSystemError> {
+    if let Some(index) = UI_ARCHIVE_INDEXES
+        .read()
+        .expect("Could not read the UI archive indexes")
+        .get(archive_path)
+    {
+        return Ok(index.clone());
+    }
+
+    let generation = UI_GENERATION.load(Ordering::SeqCst);
+
+    let mut archive = ZipArchive::new(File::open(archive_path)?)?;
+
+    let mut entries = HashMap::new();
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i)?;
+        if file.is_dir() {
+            continue;
+        }
+        entries.insert(
+            file.name().to_string(),
+            ArchiveEntry {
+                data_start: file.data_start(),
+                compressed_size: file.compressed_size(),
+                size: file.size(),
+                crc32: file.crc32(),
+                compression: file.compression(),
+            },
+        );
+    }
+
+    let index = Arc::new(UiArchiveIndex {
+        archive_path: archive_path.to_path_buf(),
+        entries,
+    });
+
+    // The zip may have been replaced while it was read, in which case the index is only used for this request
+    let mut indexes = UI_ARCHIVE_INDEXES
+        .write()
+        .expect("Could not write the UI archive indexes");
+    if UI_GENERATION.load(Ordering::SeqCst) == generation {
+        indexes.insert(archive_path.to_path_buf(), index.clone());
+    }
+
+    Ok(index)
+}
+
+/// Discards the index of the UI zip, to be called whenever the zip is replaced or removed
+pub fn forget_archive_index(archive_path: &Path) {
+    UI_ARCHIVE_INDEXES
+        .write()
+        .expect("Could not write the UI archive indexes")
+        .remove(archive_path);
+}
+
+impl UiArchiveIndex {
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(&normalize_name(name))
+    }
+
+    /// Reads the file from the archive. If `gzip` is set and the file is deflated in the archive,
+    /// its compressed data is returned as is, wrapped as gzip, together with `true`
+    pub fn read_file(
+        &self,
+        name: &str,
+        gzip: bool,
+    ) -> Result<Option<(Vec<u8>, bool)>, FileSystemError> {
+        let name = normalize_name(name);
+        let Some(entry) = self.entries.get(&name) else {
+            return Ok(None);
+        };
+
+        match entry.compression {
+            CompressionMethod::Stored => Ok(Some((self.read_raw(entry)?, false))),
+            CompressionMethod::Deflated if gzip => {
+                let deflated = self.read_raw(entry)?;
+                Ok(Some((gzip_from_deflate(deflated, entry), true)))
+            }
+            CompressionMethod::Deflated => {
+                let deflated = self.read_raw(entry)?;
+                let mut content = Vec::with_capacity(entry.size as usize);
+                flate2::read::DeflateDecoder::new(deflated.as_slice()).read_to_end(&mut content)?;
+                Ok(Some((content, false)))
+            }
+            _ => {
+                // Other compression methods are rare in UI zips, let the zip crate decompress them
+                let mut archive = ZipArchive::new(File::open(&self.archive_path)?)?;
+                let mut file = archive.by_name(name.as_str())?;
+                let mut content = Vec::with_capacity(entry.size as usize);
+                file.read_to_end(&mut content)?;
+                Ok(Some((content, false)))
+            }
+        }
+    }
+
+    fn read_raw(&self, entry: &ArchiveEntry) -> Result<Vec<u8>, FileSystemError> {
+        let mut file = File::open(&self.archive_path)?;
+        file.seek(SeekFrom::Start(entry.data_start))?;
+
+        let mut data = Vec::with_capacity(entry.compressed_size as usize);
+        file.take(entry.compressed_size).read_to_end(&mut data)?;
+
+        Ok(data)
+    }
+}
+
+/// Asset names can come with the path separator of the platform, while zip entries always use `/`
+fn normalize_name(name: &str) -> String {
+    name.replace('\\', "/")
+}
+
+/// Wraps raw deflate data in a minimal gzip member, reusing the checksum stored in the zip
+fn gzip_from_deflate(deflated: Vec<u8>, entry: &ArchiveEntry) -> Vec<u8> {
+    // Magic number, deflate method, no flags, no modification time, no extra flags, unknown OS
+    let mut gzip = vec![0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff];
+    gzip.extend(deflated);
+    gzip.extend(entry.crc32.to_le_bytes());
+    gzip.extend((entry.size as u32).to_le_bytes());
+    gzip
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::{write::FileOptions, ZipWriter};
+
+    use super::*;
+
+    const INDEX_HTML: &[u8] = b"<html><body>Hello, hello, hello, hello!</body></html>";
+
+    /// Writes a zip with a stored and a deflated copy of the same file, at a path unique to the test
+    fn write_archive(test_name: &str) -> PathBuf {
+        let archive_path =
+            std::env::temp_dir().join(format!("ui-archive-{test_name}-{}.zip", std::process::id()));
+
+        let mut zip = ZipWriter::new(File::create(&archive_path).unwrap());
+        zip.start_file(
+            "stored/index.html",
+            FileOptions::default().compression_method(CompressionMethod::Stored),
+        )
+        .unwrap();
+        zip.write_all(INDEX_HTML).unwrap();
+        zip.start_file(
+            "deflated/index.html",
+            FileOptions::default().compression_method(CompressionMethod::Deflated),
+        )
+        .unwrap();
+        zip.write_all(INDEX_HTML).unwrap();
+        zip.finish().unwrap();
+
+        archive_path
+    }
+
+    #[test]
+    fn reads_stored_entries() {
+        let archive_path = write_archive("stored");
+        let index = archive_index(&archive_path).unwrap();
+
+        assert_eq!(
+            index.read_file("stored/index.html", false).unwrap(),
+            Some((INDEX_HTML.to_vec(), false))
+        );
+        // Stored files are never gzipped, even if the client accepts it
+        assert_eq!(
+            index.read_file("stored/index.html", true).unwrap(),
+            Some((INDEX_HTML.to_vec(), false))
+        );
+
+        remove_archive(&archive_path);
+    }
+
+    #[test]
+    fn reads_deflated_entries() {
+        let archive_path = write_archive("deflated");
+        let index = archive_index(&archive_path).unwrap();
+
+        assert_eq!(
+            index.read_file("deflated/index.html", false).unwrap(),
+            Some((INDEX_HTML.to_vec(), false))
+        );
+
+        remove_archive(&archive_path);
+    }
+
+    #[test]
+    fn wraps_deflated_entries_as_gzip() {
+        let archive_path = write_archive("gzip");
+        let index = archive_index(&archive_path).unwrap();
+
+        let (gzipped, is_gzip) = index
+            .read_file("deflated/index.html", true)
+            .unwrap()
+            .unwrap();
+        assert!(is_gzip);
+
+        let mut content = Vec::new();
+        flate2::read::GzDecoder::new(gzipped.as_slice())
+            .read_to_end(&mut content)
+            .unwrap();
+        assert_eq!(content, INDEX_HTML);
+
+        remove_archive(&archive_path);
+    }
+
+    #[test]
+    fn normalizes_names() {
+        assert_eq!(normalize_name("assets\\index.js"), "assets/index.js");
+        assert_eq!(normalize_name("assets/index.js"), "assets/index.js");
+
+        let archive_path = write_archive("normalize");
+        let index = archive_index(&archive_path).unwrap();
+
+        assert!(index.contains("stored\\index.html"));
+        assert_eq!(
+            index.read_file("stored\\index.html", false).unwrap(),
+            Some((INDEX_HTML.to_vec(), false))
+        );
+
+        remove_archive(&archive_path);
+    }
+
+    #[test]
+    fn missing_entries() {
+        let archive_path = write_archive("missing");
+        let index = archive_index(&archive_path).unwrap();
+
+        assert!(!index.contains("index.html"));
+        assert_eq!(index.read_file("index.html", true).unwrap(), None);
+        // Folders are not indexed as files
+        assert_eq!(index.read_file("stored", false).unwrap(), None);
+
+        remove_archive(&archive_path);
+    }
+
+    fn remove_archive(archive_path: &Path) {
+        forget_archive_index(archive_path);
+        let _ = std::fs::remove_file(archive_path);
+    }
+}
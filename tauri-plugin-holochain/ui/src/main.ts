@@ -28,10 +28,11 @@ attachConsole().then(() => {
 // Here we are trying to cover all platforms in different ways
 // Windows doesn't support requests of type happ://APPID
 // MacOs doesn't support requests of type http://APPID.localhost:4040
+// There is no fallback through a public DNS name like localtest.me,
+// since resolving it would send the secret token in the host to the DNS servers
 export enum IframeProtocol {
   Assets,
   LocalhostSubdomain,
-  LocaltestMe,
 }
 
 async function fetchPing(origin: string) {
@@ -66,40 +67,37 @@ export function isWindows(): boolean {
   return navigator.appVersion.includes("Win");
 }
 
-async function getIframeProtocol(httpServerPort: number) {
-  if (isWindows()) {
-    try {
-      await fetchPing(`http://ping.localhost:${httpServerPort}`);
-      return IframeProtocol.LocalhostSubdomain;
-    } catch (e) {
-      return IframeProtocol.LocaltestMe;
-    }
-  } else {
+async function getIframeProtocol(runtimeInfo: RuntimeInfo, devMode: boolean) {
+  // The ping host has the same number of labels as the app hosts, to check that they resolve
+  const pingOrigin = `http://ping.${runtimeInfo.http_server_token}.localhost:${runtimeInfo.http_server_port}`;
+  // UIs in development need the http server, which proxies the websockets of their dev server
+  if (!isWindows() && !devMode) {
     try {
       await fetchPing("happ://ping");
       return IframeProtocol.Assets;
-    } catch (e) {
-      try {
-        await fetchPing(`http://ping.localhost:${httpServerPort}`);
-        return IframeProtocol.LocalhostSubdomain;
-      } catch (e) {
-        return IframeProtocol.LocaltestMe;
-      }
-    }
+    } catch (e) {}
+  }
+  try {
+    await fetchPing(pingOrigin);
+    return IframeProtocol.LocalhostSubdomain;
+  } catch (e) {
+    throw new Error(
+      "Neither the happ protocol nor the localhost subdomains are available to serve the app's UI."
+    );
   }
 }
 
+// The http server only serves the app UIs whose host includes the secret token for this launch
 export function appOrigin(
   iframeProtocol: IframeProtocol,
   appId: string,
-  httpServerPort: number
+  runtimeInfo: RuntimeInfo
 ): string {
+  const { http_server_port, http_server_token } = runtimeInfo;
   if (iframeProtocol === IframeProtocol.Assets) {
     return `happ://${appId}`;
-  } else if (iframeProtocol === IframeProtocol.LocalhostSubdomain) {
-    return `http://${appId}.localhost:${httpServerPort}`;
   } else {
-    return `http://${appId}.localtest.me:${httpServerPort}`;
+    return `http://${appId}.${http_server_token}.localhost:${http_server_port}`;
   }
 }
 
@@ -116,24 +114,31 @@ function getAppIdFromOrigin(
 
 export interface RuntimeInfo {
   http_server_port: number;
+  http_server_token: string;
   app_port: number;
   admin_port: number;
 }
 
 const appId = (window as any).__APP_ID__;
+const devMode = (window as any).__DEV_MODE__ === true;
 
 core
   .invoke<RuntimeInfo>("plugin:holochain|get_runtime_info", {})
   .then((runtimeInfo: RuntimeInfo) => {
-    getIframeProtocol(runtimeInfo.http_server_port).then((protocol) => {
-      window.addEventListener("message", async (message) => {
-        const appId = getAppIdFromOrigin(protocol, message.origin);
+    getIframeProtocol(runtimeInfo, devMode)
+      .then((protocol) => {
+        window.addEventListener("message", async (message) => {
+          const appId = getAppIdFromOrigin(protocol, message.origin);
 
-        const response = await handleRequest(runtimeInfo, appId, message.data);
-        message.ports[0].postMessage({ type: "success", result: response });
+          const response = await handleRequest(runtimeInfo, appId, message.data);
+          message.ports[0].postMessage({ type: "success", result: response });
+        });
+        buildFrame(runtimeInfo, protocol, appId);
+      })
+      .catch((e) => {
+        console.error(e);
+        document.body.textContent = e.message;
       });
-      buildFrame(runtimeInfo, protocol, appId);
-    });
   });
 
 export type Request =
@@ -146,6 +151,11 @@ export type Request =
     }
   | {
       type: "get-locales";
+    }
+  | {
+      // Sent by the app whenever it navigates, since its route can't be read from another origin
+      type: "route-changed";
+      route: string;
     };
 
 async function handleRequest(
@@ -154,15 +164,22 @@ async function handleRequest(
   request: Request
 ) {
   switch (request.type) {
-    case "get-app-runtime-info":
+    case "get-app-runtime-info": {
+      // The token keeps other local software off the http server, so it's not shared with the app's code
+      const { http_server_token, ...appRuntimeInfo } = runtimeInfo;
       return {
         appId,
-        runtimeInfo,
+        runtimeInfo: appRuntimeInfo,
       };
+    }
     case "sign-zome-call":
       return signZomeCallTauri(request.zomeCall);
     case "get-locales":
       return core.invoke("plugin:holochain|get_locales", {});
+    case "route-changed":
+      return core.invoke("plugin:holochain|set_window_route", {
+        route: request.route,
+      });
   }
 }
 
@@ -172,11 +189,17 @@ function buildFrame(
   appId: string
 ) {
   const iframe = document.createElement("iframe");
-  const origin = appOrigin(iframeProtocol, appId, runtimeInfo.http_server_port);
+  const origin = appOrigin(iframeProtocol, appId, runtimeInfo);
 
-  iframe.src = `${origin}${window.location.search}`;
+  iframe.src = `${origin}${window.location.pathname}${window.location.search}`;
   iframe.frameBorder = "0";
   document.body.appendChild(iframe);
+
+  // Called by the runtime with the app's locale when the user changes their preferred locale.
+  // The locales are not secret, and the origin of the happ protocol can't be targeted on every platform
+  (window as any).__LOCALE_CHANGED__ = (locale: string) => {
+    iframe.contentWindow?.postMessage({ type: "locale-changed", locale }, "*");
+  };
 }
 
 type TauriByteArray = number[]; // Tauri requires a number array instead of a Uint8Array